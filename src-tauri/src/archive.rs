@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Seek};
+
+use crate::deps::{self, DependencySuggestion};
+use crate::fingerprint;
+use crate::pe;
+use crate::version_info;
+use crate::yaml_generator::NestedInstallerFile;
+
+/// Executables larger than this are classified by name only.
+const MAX_SCANNED_ENTRY: u64 = 64 * 1024 * 1024;
+/// Stops pathological archives from being scanned executable by executable.
+const MAX_SCANNED_EXECUTABLES: usize = 32;
+const MAX_RUNTIME_CONFIG: u64 = 1024 * 1024;
+/// Name parts after which a release file name only describes the build.
+const PLATFORM_SUFFIXES: &[&str] = &[
+    "x64", "x86", "amd64", "arm64", "i386", "i686", "win", "win32", "win64",
+    "windows", "portable", "msvc", "gnu", "pc",
+];
+
+/// What `.zip` installers contain, expressed in winget's nested installer terms.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveInfo {
+    pub entry_count: usize,
+    pub nested_installer_type: Option<String>,
+    pub nested_installer_files: Vec<NestedInstallerFile>,
+    pub archive_binaries_depend_on_path: bool,
+    /// Framework of a nested exe installer, used to pick its default switches.
+    pub installer_technology: Option<String>,
+    /// Runtimes the scanned binaries need; moved into the analysis result.
+    #[serde(skip)]
+    pub dependencies: Vec<DependencySuggestion>,
+}
+
+struct Candidate {
+    path: String,
+    depth: usize,
+    installer_type: &'static str,
+    technology: Option<&'static str>,
+}
+
+fn extension(path: &str) -> String {
+    path.rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn directory(path: &str) -> &str {
+    path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+/// Helpers shipped next to the real binaries that should never be exposed as commands:
+/// uninstallers, updaters (e.g. Squirrel's `Update.exe`) and crash reporters.
+fn is_auxiliary_executable(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    lower.starts_with("unins")
+        || lower.starts_with("uninstall")
+        || lower.contains("update")
+        || lower.contains("crashpad")
+        || lower.contains("crashreporter")
+        || lower.contains("crash_handler")
+}
+
+/// Command a portable executable is exposed as: its lowercased name without the
+/// version, platform or architecture suffixes releases tend to add.
+pub fn command_alias(name: &str) -> String {
+    let stem = file_name(&name.replace('\\', "/")).to_ascii_lowercase();
+    let stem = stem.strip_suffix(".exe").unwrap_or(&stem).to_string();
+    let is_suffix = |part: &str| {
+        part.starts_with(|c: char| c.is_ascii_digit())
+            || (part.starts_with('v') && part[1..].starts_with(|c: char| c.is_ascii_digit()))
+            || PLATFORM_SUFFIXES.contains(&part)
+    };
+    let kept: Vec<&str> = stem
+        .split(['-', '_', '.', ' '])
+        .take_while(|part| !is_suffix(part))
+        .collect();
+    if kept.is_empty() {
+        stem
+    } else {
+        kept.join("-")
+    }
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, index: usize, limit: u64) -> Option<Vec<u8>> {
+    let mut entry = archive.by_index(index).ok()?;
+    if entry.size() > limit {
+        return None;
+    }
+    let mut data = Vec::with_capacity(entry.size() as usize);
+    entry.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// What scanning a nested executable revealed.
+struct ScannedExecutable {
+    technology: Option<fingerprint::Technology>,
+    portable: bool,
+    dependencies: Vec<DependencySuggestion>,
+}
+
+/// Fingerprints a nested executable by decompressing it into memory, and lists the
+/// runtimes it needs that `shipped` DLLs do not provide.
+fn scan_executable<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    index: usize,
+    name: &str,
+    shipped: &[String],
+) -> Option<ScannedExecutable> {
+    let data = read_entry(archive, index, MAX_SCANNED_ENTRY)?;
+    let mut cursor = Cursor::new(data);
+    let header = pe::read_header(&mut cursor)?;
+    let technology = fingerprint::detect(&mut cursor, &header);
+    let version_info = version_info::read(&mut cursor, &header);
+    Some(ScannedExecutable {
+        technology,
+        portable: technology.is_none()
+            && fingerprint::is_portable(&mut cursor, &header, name, version_info.as_ref()),
+        dependencies: deps::from_pe(&mut cursor, &header, shipped),
+    })
+}
+
+/// Lists the archive and suggests how winget should treat its contents.
+pub fn analyze<R: Read + Seek>(reader: R) -> Option<ArchiveInfo> {
+    let mut archive = zip::ZipArchive::new(reader).ok()?;
+
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let Ok(entry) = archive.by_index(i) else {
+            continue;
+        };
+        let path = entry.name().replace('\\', "/");
+        if entry.is_dir() || path.starts_with("__MACOSX/") {
+            continue;
+        }
+        files.push((i, path));
+    }
+    let shipped_dlls: Vec<String> = files
+        .iter()
+        .filter(|(_, path)| extension(path) == "dll")
+        .map(|(_, path)| file_name(path).to_ascii_lowercase())
+        .collect();
+
+    let mut dependencies = Vec::new();
+    for (index, path) in &files {
+        if path.to_ascii_lowercase().ends_with(".runtimeconfig.json") {
+            if let Some(data) = read_entry(&mut archive, *index, MAX_RUNTIME_CONFIG) {
+                let config = String::from_utf8_lossy(&data);
+                deps::merge(&mut dependencies, deps::from_runtime_config(&config, file_name(path)));
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    let mut scanned = 0;
+    for (index, path) in &files {
+        let name = file_name(path);
+        let depth = path.matches('/').count();
+        let installer_type = match extension(path).as_str() {
+            "msi" => "msi",
+            "msix" | "msixbundle" | "appx" | "appxbundle" => "msix",
+            "exe" if !is_auxiliary_executable(name) => {
+                // winget never runs a redistributable shipped next to the app, so it becomes a dependency
+                if let Some(dep) = deps::from_file_name(name, None) {
+                    deps::merge(&mut dependencies, vec![dep]);
+                    continue;
+                }
+                let scan = if scanned < MAX_SCANNED_EXECUTABLES {
+                    scanned += 1;
+                    scan_executable(&mut archive, *index, name, &shipped_dlls)
+                } else {
+                    None
+                };
+                let technology = scan.as_ref().and_then(|s| s.technology);
+                let installer_type = match (technology, &scan) {
+                    (Some(tech), _) => tech.installer_type(),
+                    (None, Some(scan)) if !scan.portable => "exe",
+                    (None, None) if fingerprint::looks_like_setup(name) => "exe",
+                    _ => "portable",
+                };
+                if let Some(scan) = scan {
+                    deps::merge(&mut dependencies, scan.dependencies);
+                }
+                candidates.push(Candidate {
+                    path: path.clone(),
+                    depth,
+                    installer_type,
+                    technology: technology.map(|t| t.id()),
+                });
+                continue;
+            }
+            _ => continue,
+        };
+        candidates.push(Candidate {
+            path: path.clone(),
+            depth,
+            installer_type,
+            technology: None,
+        });
+    }
+
+    let mut info = ArchiveInfo {
+        entry_count: files.len(),
+        dependencies,
+        ..ArchiveInfo::default()
+    };
+
+    // A real installer wins over loose executables; winget only allows one of them
+    let installer = candidates
+        .iter()
+        .filter(|c| c.installer_type != "portable")
+        .min_by_key(|c| c.depth);
+    if let Some(installer) = installer {
+        info.nested_installer_type = Some(installer.installer_type.to_string());
+        info.installer_technology = installer.technology.map(|t| t.to_string());
+        info.nested_installer_files = vec![NestedInstallerFile {
+            relative_file_path: installer.path.replace('/', "\\"),
+            portable_command_alias: None,
+        }];
+        return Some(info);
+    }
+
+    // Portable tools: expose the executables closest to the archive root
+    let Some(depth) = candidates.iter().map(|c| c.depth).min() else {
+        return Some(info);
+    };
+    let portables: Vec<&Candidate> = candidates.iter().filter(|c| c.depth == depth).collect();
+    info.nested_installer_type = Some("portable".to_string());
+    info.nested_installer_files = portables
+        .iter()
+        .map(|c| {
+            NestedInstallerFile {
+                relative_file_path: c.path.replace('/', "\\"),
+                portable_command_alias: Some(command_alias(&c.path)),
+            }
+        })
+        .collect();
+    // Binaries that load sibling DLLs break when winget symlinks them individually
+    info.archive_binaries_depend_on_path = portables.iter().any(|c| {
+        let dir = directory(&c.path);
+        files
+            .iter()
+            .any(|(_, path)| directory(path) == dir && extension(path) == "dll")
+    });
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helpers_are_not_commands() {
+        for name in ["unins000.exe", "Uninstall.exe", "Update.exe", "AutoUpdater.exe", "crashpad_handler.exe"] {
+            assert!(is_auxiliary_executable(name), "{}", name);
+        }
+        assert!(!is_auxiliary_executable("ripgrep.exe"));
+    }
+
+    #[test]
+    fn command_alias_drops_build_suffixes() {
+        assert_eq!(command_alias("rg.exe"), "rg");
+        assert_eq!(command_alias("bin/My_Tool-v1.2.3-win-x64.exe"), "my-tool");
+        assert_eq!(command_alias("1.0.exe"), "1.0");
+    }
+}
@@ -0,0 +1,51 @@
+/// Longest file extension the manifest schema accepts.
+const MAX_FILE_EXTENSION_LENGTH: usize = 64;
+
+/// Lowercases a URL scheme, or rejects it when it is not one (`^[a-z][-a-z0-9.+]*$`).
+pub fn normalize_protocol(value: &str) -> Option<String> {
+    let protocol = value.trim().to_ascii_lowercase();
+    let mut chars = protocol.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '+'));
+    valid.then_some(protocol)
+}
+
+/// Lowercases a file extension and drops its leading dot, rejecting characters
+/// file names cannot hold.
+pub fn normalize_file_extension(value: &str) -> Option<String> {
+    let extension = value.trim().trim_start_matches('.').to_lowercase();
+    let valid = !extension.is_empty()
+        && extension.chars().count() <= MAX_FILE_EXTENSION_LENGTH
+        && !extension
+            .chars()
+            .any(|c| c.is_control() || matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|'));
+    valid.then_some(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocols_are_lowercase_url_schemes() {
+        assert_eq!(normalize_protocol(" VSCode ").as_deref(), Some("vscode"));
+        assert_eq!(normalize_protocol("ms-word.v2+x").as_deref(), Some("ms-word.v2+x"));
+        assert_eq!(normalize_protocol("2fa"), None);
+        assert_eq!(normalize_protocol("my app"), None);
+        assert_eq!(normalize_protocol("steam:"), None);
+        assert_eq!(normalize_protocol(""), None);
+    }
+
+    #[test]
+    fn file_extensions_drop_the_dot() {
+        assert_eq!(normalize_file_extension(".TXT").as_deref(), Some("txt"));
+        assert_eq!(normalize_file_extension("tar.gz").as_deref(), Some("tar.gz"));
+        assert_eq!(normalize_file_extension("  .Ünï ").as_deref(), Some("ünï"));
+        assert_eq!(normalize_file_extension("."), None);
+        assert_eq!(normalize_file_extension("a/b"), None);
+        assert_eq!(normalize_file_extension("a*"), None);
+        let longest = "x".repeat(MAX_FILE_EXTENSION_LENGTH);
+        assert_eq!(normalize_file_extension(&longest).as_deref(), Some(longest.as_str()));
+        assert_eq!(normalize_file_extension(&format!("{}x", longest)), None);
+    }
+}
@@ -0,0 +1,514 @@
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::io::{Read, Seek};
+
+use crate::pe::{self, le_u16, le_u32, PeHeader, IMAGE_DIRECTORY_ENTRY_SECURITY};
+
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+/// Certificate tables larger than this are not real Authenticode blobs.
+const MAX_CERTIFICATE_TABLE: u32 = 8 * 1024 * 1024;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+const OID_TST_INFO: &str = "1.2.840.113549.1.9.16.1.4";
+
+/// Signer of an Authenticode signature, read structurally (the signature is not verified).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    pub signed: bool,
+    pub subject: Option<String>,
+    /// The `CN` of the subject, or its `O` when there is no common name.
+    pub subject_name: Option<String>,
+    pub issuer: Option<String>,
+    /// Uppercase SHA-1 of the signer certificate, as shown by Windows.
+    pub thumbprint: Option<String>,
+    /// ISO 8601 time from the signing-time attribute or the timestamp countersignature.
+    pub signing_time: Option<String>,
+}
+
+impl SignatureInfo {
+    pub fn unsigned() -> Self {
+        SignatureInfo::default()
+    }
+
+    /// A signature blob is present but could not be decoded.
+    pub fn unreadable() -> Self {
+        SignatureInfo {
+            signed: true,
+            ..SignatureInfo::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Tlv<'a> {
+    tag: u8,
+    content: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// Reads one DER element, returning it and the bytes that follow.
+fn read_tlv(buf: &[u8]) -> Option<(Tlv<'_>, &[u8])> {
+    let tag = *buf.first()?;
+    let first = *buf.get(1)? as usize;
+    let (len, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7f;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let bytes = buf.get(2..2 + count)?;
+        let len = bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    let content = buf.get(header..end)?;
+    Some((
+        Tlv {
+            tag,
+            content,
+            raw: &buf[..end],
+        },
+        &buf[end..],
+    ))
+}
+
+fn children(content: &[u8]) -> Vec<Tlv<'_>> {
+    let mut out = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        match read_tlv(rest) {
+            Some((tlv, next)) => {
+                out.push(tlv);
+                rest = next;
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+fn decode_oid(content: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut value: u64 = 0;
+    for &b in content {
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            if parts.is_empty() {
+                let first = (value / 40).min(2);
+                parts.push(first);
+                parts.push(value - first * 40);
+            } else {
+                parts.push(value);
+            }
+            value = 0;
+        }
+    }
+    parts
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn decode_text(tlv: &Tlv) -> String {
+    match tlv.tag {
+        // BMPString
+        0x1e => {
+            let units: Vec<u16> = tlv
+                .content
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        // T61String, mostly Latin-1 in practice
+        0x14 => tlv.content.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(tlv.content).to_string(),
+    }
+}
+
+fn attribute_label(oid: &str) -> String {
+    match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.5" => "SERIALNUMBER",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "S",
+        "2.5.4.9" => "STREET",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "2.5.4.17" => "PostalCode",
+        "1.2.840.113549.1.9.1" => "E",
+        _ => return format!("OID.{}", oid),
+    }
+    .to_string()
+}
+
+/// Renders an X.501 Name the way Windows does: most specific RDN first.
+fn format_name(name: &Tlv) -> (String, Vec<(String, String)>) {
+    let mut pairs = Vec::new();
+    for rdn in children(name.content) {
+        for attr in children(rdn.content) {
+            let parts = children(attr.content);
+            if let [oid, value, ..] = parts.as_slice() {
+                if oid.tag == TAG_OID {
+                    pairs.push((attribute_label(&decode_oid(oid.content)), decode_text(value)));
+                }
+            }
+        }
+    }
+    pairs.reverse();
+    let text = pairs
+        .iter()
+        .map(|(k, v)| {
+            if v.contains(',') || v.contains('"') {
+                format!("{}=\"{}\"", k, v.replace('"', "\"\""))
+            } else {
+                format!("{}={}", k, v)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    (text, pairs)
+}
+
+fn format_time(tlv: &Tlv) -> Option<String> {
+    let text = std::str::from_utf8(tlv.content).ok()?;
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let full = match tlv.tag {
+        TAG_UTC_TIME if digits.len() >= 12 => {
+            let year: u32 = digits[..2].parse().ok()?;
+            let century = if year >= 50 { "19" } else { "20" };
+            format!("{}{}", century, digits)
+        }
+        TAG_GENERALIZED_TIME if digits.len() >= 14 => digits,
+        _ => return None,
+    };
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &full[0..4],
+        &full[4..6],
+        &full[6..8],
+        &full[8..10],
+        &full[10..12],
+        &full[12..14]
+    ))
+}
+
+/// Depth-first search that also looks inside OCTET STRINGs wrapping DER (timestamp tokens).
+fn find_time(tlv: &Tlv, depth: usize) -> Option<String> {
+    if depth > 16 {
+        return None;
+    }
+    let nested = match tlv.tag {
+        TAG_OCTET_STRING => children(tlv.content),
+        tag if tag & 0x20 != 0 => children(tlv.content),
+        _ => return None,
+    };
+
+    for (i, child) in nested.iter().enumerate() {
+        if child.tag == TAG_OID {
+            let oid = decode_oid(child.content);
+            let value = nested.get(i + 1);
+            if oid == OID_SIGNING_TIME {
+                let time = value
+                    .map(|set| children(set.content))
+                    .and_then(|items| items.first().and_then(format_time));
+                if time.is_some() {
+                    return time;
+                }
+            } else if oid == OID_TST_INFO {
+                // eContent [0] { OCTET STRING { TSTInfo } }, genTime is its fifth field
+                let tst_info = value
+                    .map(|explicit| children(explicit.content))
+                    .and_then(|items| items.first().map(|o| children(o.content)))
+                    .and_then(|items| items.first().map(|seq| children(seq.content)));
+                if let Some(time) = tst_info.and_then(|fields| fields.get(4).and_then(format_time)) {
+                    return Some(time);
+                }
+            }
+        }
+        if let Some(time) = find_time(child, depth + 1) {
+            return Some(time);
+        }
+    }
+    None
+}
+
+/// Parses a DER `ContentInfo` holding PKCS#7 `SignedData`.
+pub fn parse_pkcs7(der: &[u8]) -> Option<SignatureInfo> {
+    let (content_info, _) = read_tlv(der)?;
+    let outer = children(content_info.content);
+    let explicit = outer.iter().find(|t| t.tag == TAG_CONTEXT_0)?;
+    let (signed_data, _) = read_tlv(explicit.content)?;
+    let fields = children(signed_data.content);
+
+    let certificates = fields
+        .iter()
+        .find(|t| t.tag == TAG_CONTEXT_0)
+        .map(|t| children(t.content))
+        .unwrap_or_default();
+    let signer_infos = fields.iter().rev().find(|t| t.tag == TAG_SET)?;
+    let signer = children(signer_infos.content).into_iter().next()?;
+    let signer_fields = children(signer.content);
+
+    // issuerAndSerialNumber identifies the signing certificate among the bundled ones
+    let issuer_and_serial = signer_fields.iter().find(|t| t.tag == TAG_SEQUENCE)?;
+    let id_parts = children(issuer_and_serial.content);
+    let (signer_issuer, signer_serial) = (id_parts.first()?, id_parts.get(1)?);
+
+    let certificate = certificates.iter().find(|cert| {
+        let tbs = children(cert.content)
+            .into_iter()
+            .next()
+            .map(|t| children(t.content))
+            .unwrap_or_default();
+        let offset = usize::from(tbs.first().is_some_and(|t| t.tag == TAG_CONTEXT_0));
+        let serial = tbs.get(offset);
+        let issuer = tbs.get(offset + 2);
+        serial.is_some_and(|s| s.tag == TAG_INTEGER && s.raw == signer_serial.raw)
+            && issuer.is_some_and(|i| i.raw == signer_issuer.raw)
+    })?;
+
+    let tbs = children(children(certificate.content).first()?.content);
+    let offset = usize::from(tbs.first().is_some_and(|t| t.tag == TAG_CONTEXT_0));
+    let (issuer, _) = format_name(tbs.get(offset + 2)?);
+    let (subject, subject_pairs) = format_name(tbs.get(offset + 4)?);
+    let subject_name = ["CN", "O"].iter().find_map(|key| {
+        subject_pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    });
+
+    Some(SignatureInfo {
+        signed: true,
+        subject: Some(subject),
+        subject_name,
+        issuer: Some(issuer),
+        thumbprint: Some(format!("{:X}", Sha1::digest(certificate.raw))),
+        signing_time: find_time(&signer, 0),
+    })
+}
+
+/// Reads the first `WIN_CERTIFICATE` of the PE security directory.
+pub fn read_pe_signature<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> SignatureInfo {
+    let Some(dir) = header.data_directory(IMAGE_DIRECTORY_ENTRY_SECURITY) else {
+        return SignatureInfo::unsigned();
+    };
+    if dir.size < 8 || dir.size > MAX_CERTIFICATE_TABLE {
+        return SignatureInfo::unsigned();
+    }
+    // Unlike the other directories, this one holds a file offset rather than an RVA
+    let Some(table) = pe::read_at(reader, dir.rva as u64, dir.size as usize) else {
+        return SignatureInfo::unsigned();
+    };
+    let length = le_u32(&table, 0).unwrap_or(0) as usize;
+    if le_u16(&table, 6) != Some(WIN_CERT_TYPE_PKCS_SIGNED_DATA) || length < 8 {
+        return SignatureInfo::unsigned();
+    }
+    table
+        .get(8..length.min(table.len()))
+        .and_then(parse_pkcs7)
+        .unwrap_or_else(SignatureInfo::unreadable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pe::tests::{image, set_data_directory};
+    use std::io::Cursor;
+
+    const TAG_CONTEXT_1: u8 = 0xa1;
+    const TAG_UTF8_STRING: u8 = 0x0c;
+    const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+
+    fn der(tag: u8, parts: &[Vec<u8>]) -> Vec<u8> {
+        let content = parts.concat();
+        let mut out = vec![tag];
+        match content.len() {
+            len @ 0..=0x7f => out.push(len as u8),
+            len @ 0x80..=0xff => out.extend([0x81, len as u8]),
+            len => out.extend([0x82, (len >> 8) as u8, len as u8]),
+        }
+        out.extend(content);
+        out
+    }
+
+    fn oid(dotted: &str) -> Vec<u8> {
+        let arcs: Vec<u32> = dotted.split('.').map(|a| a.parse().unwrap()).collect();
+        let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+        for &arc in &arcs[2..] {
+            let mut bytes = vec![(arc & 0x7f) as u8];
+            let mut rest = arc >> 7;
+            while rest > 0 {
+                bytes.push((rest & 0x7f) as u8 | 0x80);
+                rest >>= 7;
+            }
+            content.extend(bytes.iter().rev());
+        }
+        der(TAG_OID, &[content])
+    }
+
+    fn name(attributes: &[(&str, &str)]) -> Vec<u8> {
+        let rdns: Vec<Vec<u8>> = attributes
+            .iter()
+            .map(|(id, value)| {
+                let text = der(TAG_UTF8_STRING, &[value.as_bytes().to_vec()]);
+                der(TAG_SET, &[der(TAG_SEQUENCE, &[oid(id), text])])
+            })
+            .collect();
+        der(TAG_SEQUENCE, &rdns)
+    }
+
+    fn integer(value: u8) -> Vec<u8> {
+        der(TAG_INTEGER, &[vec![value]])
+    }
+
+    fn certificate(serial: u8, issuer: &[u8], subject: &[u8]) -> Vec<u8> {
+        let algorithm = der(TAG_SEQUENCE, &[oid("1.2.840.113549.1.1.11")]);
+        let tbs = der(TAG_SEQUENCE, &[
+            der(TAG_CONTEXT_0, &[integer(2)]),
+            integer(serial),
+            algorithm.clone(),
+            issuer.to_vec(),
+            der(TAG_SEQUENCE, &[der(TAG_UTC_TIME, &[b"240101000000Z".to_vec()])]),
+            subject.to_vec(),
+            der(TAG_SEQUENCE, &[]),
+        ]);
+        der(TAG_SEQUENCE, &[tbs, algorithm, der(0x03, &[vec![0, 1, 2, 3]])])
+    }
+
+    fn issuer() -> Vec<u8> {
+        name(&[("2.5.4.6", "US"), ("2.5.4.3", "Contoso Code Signing CA")])
+    }
+
+    fn signer_certificate() -> Vec<u8> {
+        let subject = name(&[("2.5.4.6", "US"), ("2.5.4.10", "Contoso, Ltd."), ("2.5.4.3", "Contoso App")]);
+        certificate(9, &issuer(), &subject)
+    }
+
+    /// A `ContentInfo` whose signer is the second of two bundled certificates.
+    fn signed_data(attributes: Vec<u8>) -> Vec<u8> {
+        let issuer = issuer();
+        let other = certificate(7, &issuer, &name(&[("2.5.4.3", "Other")]));
+        let signer = signer_certificate();
+        let signer_info = der(TAG_SEQUENCE, &[
+            integer(1),
+            der(TAG_SEQUENCE, &[issuer.clone(), integer(9)]),
+            der(TAG_SEQUENCE, &[oid("2.16.840.1.101.3.4.2.1")]),
+            attributes,
+            der(TAG_SEQUENCE, &[oid("1.2.840.113549.1.1.1")]),
+            der(TAG_OCTET_STRING, &[vec![0; 4]]),
+        ]);
+        let signed = der(TAG_SEQUENCE, &[
+            integer(1),
+            der(TAG_SET, &[]),
+            der(TAG_SEQUENCE, &[oid("1.3.6.1.4.1.311.2.1.4")]),
+            der(TAG_CONTEXT_0, &[other, signer]),
+            der(TAG_SET, &[signer_info]),
+        ]);
+        der(TAG_SEQUENCE, &[oid(OID_SIGNED_DATA), der(TAG_CONTEXT_0, &[signed])])
+    }
+
+    #[test]
+    fn reads_the_signer_and_signing_time() {
+        let signing_time = der(TAG_SEQUENCE, &[
+            oid(OID_SIGNING_TIME),
+            der(TAG_SET, &[der(TAG_UTC_TIME, &[b"240315123045Z".to_vec()])]),
+        ]);
+        let pkcs7 = signed_data(der(TAG_CONTEXT_0, &[signing_time]));
+        let info = parse_pkcs7(&pkcs7).unwrap();
+        assert!(info.signed);
+        assert_eq!(info.subject.as_deref(), Some("CN=Contoso App, O=\"Contoso, Ltd.\", C=US"));
+        assert_eq!(info.subject_name.as_deref(), Some("Contoso App"));
+        assert_eq!(info.issuer.as_deref(), Some("CN=Contoso Code Signing CA, C=US"));
+        let thumbprint = format!("{:X}", Sha1::digest(signer_certificate()));
+        assert_eq!(info.thumbprint, Some(thumbprint));
+        assert_eq!(info.signing_time.as_deref(), Some("2024-03-15T12:30:45Z"));
+    }
+
+    #[test]
+    fn reads_the_timestamp_countersignature() {
+        let tst_info = der(TAG_SEQUENCE, &[
+            integer(1),
+            oid("1.2.3.4"),
+            der(TAG_SEQUENCE, &[]),
+            integer(42),
+            der(TAG_GENERALIZED_TIME, &[b"20250102030405.5Z".to_vec()]),
+        ]);
+        let token = der(TAG_SEQUENCE, &[
+            oid(OID_SIGNED_DATA),
+            der(TAG_CONTEXT_0, &[der(TAG_SEQUENCE, &[
+                integer(3),
+                der(TAG_SET, &[]),
+                der(TAG_SEQUENCE, &[
+                    oid(OID_TST_INFO),
+                    der(TAG_CONTEXT_0, &[der(TAG_OCTET_STRING, &[tst_info])]),
+                ]),
+            ])]),
+        ]);
+        let countersignature = der(TAG_SEQUENCE, &[oid("1.3.6.1.4.1.311.3.3.1"), der(TAG_SET, &[token])]);
+        let info = parse_pkcs7(&signed_data(der(TAG_CONTEXT_1, &[countersignature]))).unwrap();
+        assert_eq!(info.signing_time.as_deref(), Some("2025-01-02T03:04:05Z"));
+    }
+
+    fn signed_image(certificate_type: u16, pkcs7: &[u8]) -> Vec<u8> {
+        let mut data = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[]);
+        let offset = data.len() as u32;
+        data.extend((8 + pkcs7.len() as u32).to_le_bytes());
+        data.extend(0x0200u16.to_le_bytes());
+        data.extend(certificate_type.to_le_bytes());
+        data.extend(pkcs7);
+        data.resize(data.len().next_multiple_of(8), 0);
+        let size = data.len() as u32 - offset;
+        set_data_directory(&mut data, IMAGE_DIRECTORY_ENTRY_SECURITY, offset, size);
+        data
+    }
+
+    fn pe_signature(data: &[u8]) -> SignatureInfo {
+        let mut reader = Cursor::new(data);
+        let header = pe::read_header(&mut reader).unwrap();
+        read_pe_signature(&mut reader, &header)
+    }
+
+    #[test]
+    fn reads_the_certificate_table_of_an_image() {
+        let pkcs7 = signed_data(der(TAG_CONTEXT_0, &[]));
+        let info = pe_signature(&signed_image(WIN_CERT_TYPE_PKCS_SIGNED_DATA, &pkcs7));
+        assert_eq!(info.subject_name.as_deref(), Some("Contoso App"));
+        assert_eq!(info.signing_time, None);
+
+        assert!(!pe_signature(&image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[])).signed);
+        assert!(!pe_signature(&signed_image(0x0001, &pkcs7)).signed);
+    }
+
+    #[test]
+    fn damaged_signatures_are_unreadable() {
+        let pkcs7 = signed_data(der(TAG_CONTEXT_0, &[]));
+        assert!(parse_pkcs7(&pkcs7[..pkcs7.len() / 2]).is_none());
+        assert!(parse_pkcs7(&[TAG_SEQUENCE, 0x84, 0xff, 0xff, 0xff, 0xff]).is_none());
+        assert!(parse_pkcs7(&[]).is_none());
+
+        let info = pe_signature(&signed_image(WIN_CERT_TYPE_PKCS_SIGNED_DATA, &pkcs7[..pkcs7.len() - 20]));
+        assert!(info.signed);
+        assert_eq!(info.subject, None);
+
+        // A table running past the end of the file
+        let mut data = signed_image(WIN_CERT_TYPE_PKCS_SIGNED_DATA, &pkcs7);
+        let len = data.len();
+        data.truncate(len - 16);
+        assert!(!pe_signature(&data).signed);
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::cab::Cabinet;
+use crate::pe::{le_u32, read_at, PeHeader};
+
+const BURN_SECTION_MAGIC: u32 = 0x00f1_4300;
+const CONTAINER_FORMAT_CABINET: u32 = 1;
+/// Name of `BurnManifest.xml` inside the UX container.
+const MANIFEST_NAME: &str = "0";
+const MAX_UX_CONTAINER: u32 = 64 * 1024 * 1024;
+
+/// Registration and chain of a WiX Burn bundle, from its `BurnManifest.xml`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnInfo {
+    /// Bundle id; Burn registers the bundle under this key in Apps & Features.
+    pub bundle_id: Option<String>,
+    pub upgrade_code: Option<String>,
+    pub provider_key: Option<String>,
+    pub version: Option<String>,
+    pub display_name: Option<String>,
+    pub display_version: Option<String>,
+    pub publisher: Option<String>,
+    pub per_machine: Option<bool>,
+    pub packages: Vec<BurnPackage>,
+}
+
+/// An MSI package the bundle chains.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnPackage {
+    pub id: String,
+    pub product_code: Option<String>,
+    pub upgrade_code: Option<String>,
+    pub version: Option<String>,
+    pub display_name: Option<String>,
+    /// Whether the package shows its own Apps & Features entry next to the bundle's.
+    pub visible: bool,
+}
+
+/// Reads `BurnManifest.xml` from the UX container attached to a WiX Burn bundle.
+fn read_manifest<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<String> {
+    let section = header.sections.iter().find(|s| s.name == ".wixburn")?;
+    let burn = read_at(reader, section.raw_offset as u64, section.raw_size.min(512) as usize)?;
+    if le_u32(&burn, 0)? != BURN_SECTION_MAGIC || le_u32(&burn, 40)? != CONTAINER_FORMAT_CABINET {
+        return None;
+    }
+    // Containers follow the engine stub; the UX one comes first
+    let stub_size = le_u32(&burn, 24)?;
+    let ux_size = le_u32(&burn, 48).filter(|_| le_u32(&burn, 44).is_some_and(|count| count > 0))?;
+    if ux_size > MAX_UX_CONTAINER {
+        return None;
+    }
+    let container = read_at(reader, stub_size as u64, ux_size as usize)?;
+    let manifest = Cabinet::parse(&container)?.read(MANIFEST_NAME)?;
+    Some(
+        String::from_utf8_lossy(&manifest)
+            .trim_start_matches('\u{feff}')
+            .to_string(),
+    )
+}
+
+fn is_yes(value: &str) -> bool {
+    value.eq_ignore_ascii_case("yes")
+}
+
+/// Reads the registration, upgrade code and chained MSI packages of a Burn manifest.
+pub fn parse_manifest(manifest: &str) -> Option<BurnInfo> {
+    let doc = roxmltree::Document::parse(manifest).ok()?;
+    let element = |name: &str| doc.descendants().find(|n| n.tag_name().name() == name);
+    let attribute = |node: Option<roxmltree::Node>, name: &str| {
+        node.and_then(|n| n.attribute(name)).filter(|v| !v.is_empty()).map(|v| v.to_string())
+    };
+
+    let registration = element("Registration");
+    let arp = registration.and_then(|r| r.children().find(|n| n.tag_name().name() == "Arp"));
+    let upgrade = doc.descendants().find(|n| {
+        n.tag_name().name() == "RelatedBundle"
+            && n.attribute("Action").is_some_and(|a| a.eq_ignore_ascii_case("upgrade"))
+    });
+    // WiX 5 renamed the bundle id to `Code`
+    let bundle_id = attribute(registration, "Id").or_else(|| attribute(registration, "Code"));
+
+    let packages = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "MsiPackage")
+        .map(|n| {
+            let provides = n.children().find(|c| c.tag_name().name() == "Provides");
+            // Burn hides chained MSIs unless the bundle authored them as visible
+            let hidden = n.children().any(|c| {
+                c.tag_name().name() == "MsiProperty"
+                    && c.attribute("Id") == Some("ARPSYSTEMCOMPONENT")
+                    && c.attribute("Value") == Some("1")
+            });
+            BurnPackage {
+                id: n.attribute("Id").unwrap_or_default().to_string(),
+                product_code: attribute(Some(n), "ProductCode"),
+                upgrade_code: attribute(Some(n), "UpgradeCode"),
+                version: attribute(Some(n), "Version"),
+                display_name: attribute(provides, "DisplayName"),
+                visible: !hidden,
+            }
+        })
+        .collect();
+
+    Some(BurnInfo {
+        provider_key: attribute(registration, "ProviderKey").or_else(|| bundle_id.clone()),
+        bundle_id,
+        upgrade_code: attribute(upgrade, "Id").or_else(|| attribute(upgrade, "Code")),
+        version: attribute(registration, "Version"),
+        display_name: attribute(arp, "DisplayName"),
+        display_version: attribute(arp, "DisplayVersion"),
+        publisher: attribute(arp, "Publisher"),
+        per_machine: registration.and_then(|r| r.attribute("PerMachine")).map(is_yes),
+        packages,
+    })
+}
+
+/// Reads a Burn bundle's manifest from its attached UX container.
+pub fn analyze<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<BurnInfo> {
+    parse_manifest(&read_manifest(reader, header)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<BurnManifest xmlns="http://wixtoolset.org/schemas/v4/2008/Burn">
+  <RelatedBundle Id="{5C1E9A1F-0D27-4B7F-9F0B-3C2D6E8A4B10}" Action="Detect" />
+  <RelatedBundle Id="{AD1B2F6E-7C43-4E51-8D2A-91F0C3B5E7D2}" Action="Upgrade" />
+  <Registration Id="{0E4B7E2C-3A9D-4F61-B8C5-2D7A1F9E6C34}" ExecutableName="ContosoSetup.exe" PerMachine="yes" Tag="" Version="2.4.1.0" ProviderKey="Contoso.App">
+    <Arp Register="yes" DisplayName="Contoso App" DisplayVersion="2.4.1" Publisher="Contoso Ltd." />
+  </Registration>
+  <Chain>
+    <MsiPackage Id="Runtime" ProductCode="{8F3C2B1A-6D5E-4C7B-9A0F-1E2D3C4B5A69}" UpgradeCode="{B2C3D4E5-F6A7-4B8C-9D0E-1F2A3B4C5D6E}" Version="1.2.0.0">
+      <MsiProperty Id="ARPSYSTEMCOMPONENT" Value="1" />
+      <Provides Key="{8F3C2B1A-6D5E-4C7B-9A0F-1E2D3C4B5A69}" DisplayName="Contoso Runtime" />
+    </MsiPackage>
+    <MsiPackage Id="App" ProductCode="{1A2B3C4D-5E6F-4A7B-8C9D-0E1F2A3B4C5D}" Version="2.4.1.0">
+      <Provides Key="{1A2B3C4D-5E6F-4A7B-8C9D-0E1F2A3B4C5D}" DisplayName="Contoso App Core" />
+    </MsiPackage>
+  </Chain>
+</BurnManifest>"#;
+
+    #[test]
+    fn manifest_gives_registration_and_chain() {
+        let info = parse_manifest(MANIFEST).unwrap();
+        assert_eq!(info.bundle_id.as_deref(), Some("{0E4B7E2C-3A9D-4F61-B8C5-2D7A1F9E6C34}"));
+        assert_eq!(info.provider_key.as_deref(), Some("Contoso.App"));
+        assert_eq!(info.upgrade_code.as_deref(), Some("{AD1B2F6E-7C43-4E51-8D2A-91F0C3B5E7D2}"));
+        assert_eq!(info.version.as_deref(), Some("2.4.1.0"));
+        assert_eq!(info.display_name.as_deref(), Some("Contoso App"));
+        assert_eq!(info.display_version.as_deref(), Some("2.4.1"));
+        assert_eq!(info.publisher.as_deref(), Some("Contoso Ltd."));
+        assert_eq!(info.per_machine, Some(true));
+
+        let [runtime, app] = info.packages.as_slice() else {
+            panic!("expected two packages, got {:?}", info.packages);
+        };
+        assert_eq!(runtime.id, "Runtime");
+        assert_eq!(runtime.upgrade_code.as_deref(), Some("{B2C3D4E5-F6A7-4B8C-9D0E-1F2A3B4C5D6E}"));
+        assert_eq!(runtime.display_name.as_deref(), Some("Contoso Runtime"));
+        assert!(!runtime.visible);
+        assert_eq!(app.product_code.as_deref(), Some("{1A2B3C4D-5E6F-4A7B-8C9D-0E1F2A3B4C5D}"));
+        assert_eq!(app.upgrade_code, None);
+        assert!(app.visible);
+    }
+
+    #[test]
+    fn wix5_manifest_uses_codes() {
+        let manifest = r#"<BurnManifest>
+  <RelatedBundle Code="{AD1B2F6E-7C43-4E51-8D2A-91F0C3B5E7D2}" Action="upgrade" />
+  <Registration Code="{0E4B7E2C-3A9D-4F61-B8C5-2D7A1F9E6C34}" PerMachine="no" Version="3.0.0.0" />
+</BurnManifest>"#;
+        let info = parse_manifest(manifest).unwrap();
+        assert_eq!(info.bundle_id.as_deref(), Some("{0E4B7E2C-3A9D-4F61-B8C5-2D7A1F9E6C34}"));
+        // Without a provider key, Burn registers the bundle under its id
+        assert_eq!(info.provider_key, info.bundle_id);
+        assert_eq!(info.upgrade_code.as_deref(), Some("{AD1B2F6E-7C43-4E51-8D2A-91F0C3B5E7D2}"));
+        assert_eq!(info.per_machine, Some(false));
+        assert!(info.packages.is_empty());
+
+        assert!(parse_manifest("<BurnManifest><Registration").is_none());
+    }
+}
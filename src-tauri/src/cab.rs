@@ -0,0 +1,191 @@
+use flate2::{Decompress, FlushDecompress};
+
+use crate::pe::{le_u16, le_u32};
+
+const COMPRESSION_NONE: u16 = 0;
+const COMPRESSION_MSZIP: u16 = 1;
+const FLAG_PREV_CABINET: u16 = 0x0001;
+const FLAG_NEXT_CABINET: u16 = 0x0002;
+const FLAG_RESERVE_PRESENT: u16 = 0x0004;
+/// MSZIP blocks may refer back this far into the output of the previous block.
+const MSZIP_WINDOW: usize = 32 * 1024;
+/// Files larger than this are not extracted into memory.
+const MAX_FILE_SIZE: u32 = 64 * 1024 * 1024;
+
+struct Folder {
+    data_offset: usize,
+    block_count: u16,
+    compression: u16,
+}
+
+struct File {
+    name: String,
+    size: u32,
+    folder_offset: u32,
+    folder: u16,
+}
+
+/// An in-memory Microsoft cabinet; only stored and MSZIP folders can be read.
+pub struct Cabinet<'a> {
+    data: &'a [u8],
+    folders: Vec<Folder>,
+    files: Vec<File>,
+    data_reserve: usize,
+}
+
+fn skip_c_string(data: &[u8], pos: usize) -> Option<usize> {
+    Some(pos + data.get(pos..)?.iter().position(|&b| b == 0)? + 1)
+}
+
+/// Inflates one MSZIP block. A non-final stored block replaying the previous output is
+/// put in front, so back-references into the shared window resolve.
+fn inflate_mszip_block(block: &[u8], history: &[u8], expected: usize) -> Option<Vec<u8>> {
+    let block = block.strip_prefix(b"CK")?;
+    let window = &history[history.len().saturating_sub(MSZIP_WINDOW)..];
+    let mut input = Vec::with_capacity(5 + window.len() + block.len());
+    input.push(0);
+    input.extend_from_slice(&(window.len() as u16).to_le_bytes());
+    input.extend_from_slice(&(!(window.len() as u16)).to_le_bytes());
+    input.extend_from_slice(window);
+    input.extend_from_slice(block);
+
+    let mut out = Vec::with_capacity(window.len() + expected);
+    Decompress::new(false)
+        .decompress_vec(&input, &mut out, FlushDecompress::Finish)
+        .ok()?;
+    out.drain(..window.len());
+    (out.len() == expected).then_some(out)
+}
+
+impl<'a> Cabinet<'a> {
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        if !data.starts_with(b"MSCF") {
+            return None;
+        }
+        let files_offset = le_u32(data, 16)? as usize;
+        let folder_count = le_u16(data, 26)? as usize;
+        let file_count = le_u16(data, 28)? as usize;
+        let flags = le_u16(data, 30)?;
+
+        let mut pos = 36;
+        let (mut folder_reserve, mut data_reserve) = (0, 0);
+        if flags & FLAG_RESERVE_PRESENT != 0 {
+            let header_reserve = le_u16(data, 36)? as usize;
+            folder_reserve = *data.get(38)? as usize;
+            data_reserve = *data.get(39)? as usize;
+            pos = 40 + header_reserve;
+        }
+        for flag in [FLAG_PREV_CABINET, FLAG_NEXT_CABINET] {
+            if flags & flag != 0 {
+                // Cabinet name, then disk name
+                pos = skip_c_string(data, skip_c_string(data, pos)?)?;
+            }
+        }
+
+        let folders = (0..folder_count)
+            .map(|i| {
+                let at = pos + i * (8 + folder_reserve);
+                Some(Folder {
+                    data_offset: le_u32(data, at)? as usize,
+                    block_count: le_u16(data, at + 4)?,
+                    compression: le_u16(data, at + 6)? & 0x000f,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut files = Vec::with_capacity(file_count);
+        let mut pos = files_offset;
+        for _ in 0..file_count {
+            let end = skip_c_string(data, pos + 16)?;
+            files.push(File {
+                size: le_u32(data, pos)?,
+                folder_offset: le_u32(data, pos + 4)?,
+                folder: le_u16(data, pos + 8)?,
+                name: String::from_utf8_lossy(&data[pos + 16..end - 1]).to_string(),
+            });
+            pos = end;
+        }
+
+        Some(Cabinet {
+            data,
+            folders,
+            files,
+            data_reserve,
+        })
+    }
+
+    pub fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let file = self.files.iter().find(|f| f.name == name)?;
+        if file.size > MAX_FILE_SIZE {
+            return None;
+        }
+        // Files continued from another cabinet use special folder indexes and are skipped
+        let folder = self.folders.get(file.folder as usize)?;
+        let start = file.folder_offset as usize;
+        let end = start + file.size as usize;
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut pos = folder.data_offset;
+        for _ in 0..folder.block_count {
+            if output.len() >= end {
+                break;
+            }
+            let compressed = le_u16(self.data, pos + 4)? as usize;
+            let uncompressed = le_u16(self.data, pos + 6)? as usize;
+            let block_start = pos + 8 + self.data_reserve;
+            let block = self.data.get(block_start..block_start + compressed)?;
+            match folder.compression {
+                COMPRESSION_NONE => output.extend_from_slice(block),
+                COMPRESSION_MSZIP => {
+                    let inflated = inflate_mszip_block(block, &output, uncompressed)?;
+                    output.extend_from_slice(&inflated);
+                }
+                _ => return None,
+            }
+            pos = block_start + compressed;
+        }
+        output.get(start..end).map(|bytes| bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two files in one MSZIP folder of three blocks; `data.bin` starts in the first
+    /// block and repeats a 3000-byte pattern, so the later blocks only hold references
+    /// back into the previous block's output.
+    const FIXTURE: &[u8] = include_bytes!("../tests/fixtures/mszip.cab");
+
+    #[test]
+    fn reads_files_across_mszip_blocks() {
+        let cabinet = Cabinet::parse(FIXTURE).unwrap();
+        assert_eq!(cabinet.read("readme.txt").unwrap(), b"UniCreate MSZIP fixture\r\n");
+
+        let data = cabinet.read("data.bin").unwrap();
+        assert_eq!(data.len(), 70_000);
+        assert!(data[3000..].iter().zip(&data).all(|(a, b)| a == b));
+        let mut crc = flate2::Crc::new();
+        crc.update(&data);
+        assert_eq!(crc.sum(), 0xcdc9_21b5);
+
+        assert_eq!(cabinet.read("missing.txt"), None);
+    }
+
+    #[test]
+    fn damaged_cabinets_read_nothing() {
+        assert!(Cabinet::parse(&FIXTURE[..40]).is_none());
+        assert!(Cabinet::parse(b"MSCX").is_none());
+
+        // The last block is cut off
+        let truncated = Cabinet::parse(&FIXTURE[..FIXTURE.len() - 10]).unwrap();
+        assert_eq!(truncated.read("data.bin"), None);
+        assert!(truncated.read("readme.txt").is_some());
+
+        // A block without the MSZIP signature
+        let mut corrupt = FIXTURE.to_vec();
+        let second_block = FIXTURE.len() - 45 - 8 - 211;
+        corrupt[second_block..second_block + 2].copy_from_slice(b"XX");
+        assert_eq!(Cabinet::parse(&corrupt).unwrap().read("data.bin"), None);
+    }
+}
@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::hash::HashResult;
+
+/// Bumped whenever the analysis changes, so old results are recomputed instead of reused.
+pub const ANALYSIS_VERSION: u32 = 11;
+
+const MAX_ENTRIES: usize = 256;
+/// Total size of the installers kept next to their results.
+const MAX_CACHED_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+/// Larger installers only get their result cached.
+const MAX_CACHED_FILE: u64 = 1024 * 1024 * 1024;
+
+static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// An analyzed download, valid for as long as the server reports the same validators.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_length: u64,
+    pub analysis_version: u32,
+    pub result: HashResult,
+    /// Name of the cached installer inside the cache directory, if it was kept.
+    pub file: Option<String>,
+    /// Unix time of the last hit, for LRU eviction.
+    pub last_used: u64,
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("UniCreate").join("hash-cache"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn url_key(url: &str) -> String {
+    format!("{:x}", Sha256::digest(url.as_bytes()))
+}
+
+/// Loads the index entry by entry, so one unreadable entry (e.g. written by another
+/// version) does not throw the others away; its kept file is deleted with it. Also
+/// tells whether any entry was dropped.
+fn load_index(dir: &Path) -> (HashMap<String, CacheEntry>, bool) {
+    let raw: HashMap<String, serde_json::Value> = std::fs::read(dir.join("index.json"))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default();
+    let read = raw.len();
+    let index: HashMap<String, CacheEntry> = raw
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let file = value.get("file").and_then(|f| f.as_str()).map(|f| f.to_string());
+            match serde_json::from_value::<CacheEntry>(value) {
+                Ok(entry) => Some((key, entry)),
+                Err(_) => {
+                    if let Some(file) = file {
+                        let _ = std::fs::remove_file(dir.join(file));
+                    }
+                    let _ = std::fs::remove_file(dir.join(icons_file(&key)));
+                    None
+                }
+            }
+        })
+        .collect();
+    let pruned = index.len() != read;
+    (index, pruned)
+}
+
+fn save_index(dir: &Path, index: &HashMap<String, CacheEntry>) -> Result<(), String> {
+    let data = serde_json::to_vec(index).map_err(|e| format!("Cache index error: {}", e))?;
+    // Write then rename so a crash never leaves a truncated index behind
+    let temp = dir.join("index.json.tmp");
+    std::fs::write(&temp, data).map_err(|e| format!("Cache write error: {}", e))?;
+    std::fs::rename(&temp, dir.join("index.json")).map_err(|e| format!("Cache write error: {}", e))
+}
+
+/// Runs `f` on the index while holding the cache lock. `f` also returns whether it
+/// changed the index, which is only written back in that case.
+fn with_index<T>(f: impl FnOnce(&Path, &mut HashMap<String, CacheEntry>) -> (T, bool)) -> Option<T> {
+    let _guard = LOCK.get_or_init(|| Mutex::new(())).lock().ok()?;
+    let dir = cache_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    let (mut index, pruned) = load_index(&dir);
+    let (value, changed) = f(&dir, &mut index);
+    if changed || pruned {
+        save_index(&dir, &index).ok()?;
+    }
+    Some(value)
+}
+
+fn remove_entry(dir: &Path, index: &mut HashMap<String, CacheEntry>, key: &str) {
+    if let Some(file) = index.remove(key).and_then(|entry| entry.file) {
+        let _ = std::fs::remove_file(dir.join(file));
+    }
+    let _ = std::fs::remove_file(dir.join(icons_file(key)));
+}
+
+fn icons_file(key: &str) -> String {
+    format!("{}.icons.json", key)
+}
+
+/// Copy of `result` for the index, with the icon images moved to their own file so the
+/// index, rewritten on every store, stays small.
+fn without_icon_data(dir: &Path, key: &str, result: &HashResult) -> HashResult {
+    let mut result = result.clone();
+    let images: Vec<String> = result
+        .icons
+        .iter_mut()
+        .map(|icon| std::mem::take(&mut icon.data))
+        .collect();
+    let path = dir.join(icons_file(key));
+    let written = !images.is_empty()
+        && serde_json::to_vec(&images).is_ok_and(|data| std::fs::write(&path, data).is_ok());
+    if !written {
+        let _ = std::fs::remove_file(&path);
+        result.icons.clear();
+    }
+    result
+}
+
+/// Puts back the icon images `without_icon_data` set aside.
+fn restore_icon_data(dir: &Path, key: &str, result: &mut HashResult) {
+    if result.icons.is_empty() {
+        return;
+    }
+    let images: Vec<String> = std::fs::read(dir.join(icons_file(key)))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default();
+    if images.len() != result.icons.len() {
+        result.icons.clear();
+        return;
+    }
+    for (icon, data) in result.icons.iter_mut().zip(images) {
+        icon.data = data;
+    }
+}
+
+/// Drops least recently used entries until both the entry count and the file budget fit.
+fn evict(dir: &Path, index: &mut HashMap<String, CacheEntry>) {
+    let mut by_age: Vec<(u64, String)> = index
+        .iter()
+        .map(|(key, entry)| (entry.last_used, key.clone()))
+        .collect();
+    by_age.sort();
+
+    let file_bytes = |index: &HashMap<String, CacheEntry>| -> u64 {
+        index
+            .values()
+            .filter(|e| e.file.is_some())
+            .map(|e| e.content_length)
+            .sum()
+    };
+    for (_, key) in by_age {
+        if index.len() <= MAX_ENTRIES && file_bytes(index) <= MAX_CACHED_BYTES {
+            break;
+        }
+        remove_entry(dir, index, &key);
+    }
+}
+
+pub fn lookup(url: &str) -> Option<CacheEntry> {
+    with_index(|dir, index| {
+        let key = url_key(url);
+        let Some(mut entry) = index.get(&key).cloned() else {
+            return (None, false);
+        };
+        // A kept file that has since disappeared makes the whole entry suspect
+        if entry.file.as_ref().is_some_and(|f| !dir.join(f).exists()) {
+            remove_entry(dir, index, &key);
+            return (None, true);
+        }
+        restore_icon_data(dir, &key, &mut entry.result);
+        (Some(entry), false)
+    })
+    .flatten()
+}
+
+/// Path of the installer kept for `entry`, if any.
+pub fn file_path(entry: &CacheEntry) -> Option<PathBuf> {
+    Some(cache_dir()?.join(entry.file.as_ref()?))
+}
+
+/// Records a hit, optionally replacing the stored result (after re-analysis).
+pub fn touch(url: &str, result: Option<&HashResult>) {
+    with_index(|dir, index| {
+        let key = url_key(url);
+        let Some(entry) = index.get_mut(&key) else {
+            return ((), false);
+        };
+        entry.last_used = now();
+        if let Some(result) = result {
+            entry.result = without_icon_data(dir, &key, result);
+            entry.analysis_version = ANALYSIS_VERSION;
+        }
+        ((), true)
+    });
+}
+
+/// Stores a fresh result; `file` is moved into the cache when it fits the size budget.
+pub fn store(
+    url: &str,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    result: &HashResult,
+    file: Option<&Path>,
+) {
+    with_index(|dir, index| {
+        let key = url_key(url);
+        remove_entry(dir, index, &key);
+
+        let kept = file.filter(|_| result.file_size <= MAX_CACHED_FILE).and_then(|path| {
+            let name = format!("{}.bin", key);
+            let target = dir.join(&name);
+            let moved = std::fs::rename(path, &target).is_ok()
+                || std::fs::copy(path, &target).is_ok_and(|_| std::fs::remove_file(path).is_ok());
+            moved.then_some(name)
+        });
+
+        let stored = without_icon_data(dir, &key, result);
+        index.insert(
+            key,
+            CacheEntry {
+                url: url.to_string(),
+                etag,
+                last_modified,
+                content_length: result.file_size,
+                analysis_version: ANALYSIS_VERSION,
+                result: stored,
+                file: kept,
+                last_used: now(),
+            },
+        );
+        evict(dir, index);
+        ((), true)
+    });
+}
+
+pub fn clear() -> Result<(), String> {
+    let _guard = LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .map_err(|_| "Cache is busy".to_string())?;
+    let Some(dir) = cache_dir() else {
+        return Ok(());
+    };
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Cannot clear cache: {}", e)),
+    }
+}
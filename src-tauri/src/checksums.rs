@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChecksumStatus {
+    Match,
+    Mismatch,
+    /// GitHub refused to list the release, usually because of its API rate limit.
+    Unverified,
+}
+
+/// Outcome of comparing a download against the checksum files of its GitHub release.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumCheck {
+    /// Release asset the expected hash was read from, or the API that refused the lookup.
+    pub source: String,
+    /// Absent when the check was `unverified`.
+    pub expected: Option<String>,
+    pub status: ChecksumStatus,
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Names as written in checksum lists may carry `./` or a directory.
+fn listed_name(name: &str) -> &str {
+    let name = name.trim();
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
+}
+
+/// Checksum lists covering the whole release, plus per-file ones for `file_name` only.
+fn is_checksum_asset(name: &str, file_name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    let file_name = file_name.to_ascii_lowercase();
+    let per_file = [".sha256", ".sha256sum", ".sha256.txt"]
+        .iter()
+        .any(|ext| lower.strip_suffix(ext) == Some(file_name.as_str()));
+    per_file
+        || lower.contains("sha256sums")
+        || lower.contains("checksums")
+        || lower == "sha256.txt"
+        || lower == "hashes.txt"
+}
+
+/// Parses one line in GNU coreutils (`<hash>  name`, `<hash> *name`) or BSD
+/// (`SHA256 (name) = <hash>`) format into `(name, hash)`.
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    // GNU marks names containing backslashes or newlines with a leading backslash
+    let line = line.trim().trim_start_matches('\\');
+    for prefix in ["SHA256 (", "SHA256(", "SHA2-256(", "SHA2-256 ("] {
+        if let Some(rest) = line.strip_prefix(prefix) {
+            let (name, hash) = rest.rsplit_once(')')?;
+            let hash = hash.trim().strip_prefix('=')?.trim();
+            return is_sha256(hash).then_some((name, hash));
+        }
+    }
+    let (hash, name) = line.split_once(char::is_whitespace)?;
+    let name = name.trim_start().trim_start_matches('*');
+    (is_sha256(hash) && !name.is_empty()).then_some((name, hash))
+}
+
+/// Finds the hash listed for `file_name`; a per-file asset may also hold nothing but the hash.
+fn expected_hash(content: &str, asset_name: &str, file_name: &str) -> Option<String> {
+    let lines: Vec<(&str, &str)> = content.lines().filter_map(parse_line).collect();
+    let listed = lines
+        .iter()
+        .find(|(name, _)| listed_name(name) == file_name)
+        .or_else(|| {
+            lines
+                .iter()
+                .find(|(name, _)| listed_name(name).eq_ignore_ascii_case(file_name))
+        })
+        .map(|(_, hash)| hash.to_string());
+    if listed.is_some() {
+        return listed;
+    }
+
+    let per_file = asset_name
+        .to_ascii_lowercase()
+        .starts_with(&format!("{}.", file_name.to_ascii_lowercase()));
+    if !per_file {
+        return None;
+    }
+    content
+        .split_whitespace()
+        .next()
+        .filter(|token| is_sha256(token))
+        .map(|token| token.to_string())
+}
+
+/// Cross-checks `sha256` against the checksum assets of the GitHub release `url`
+/// points at, reading the release with `token` when given. `None` when the URL is not a
+/// release download or nothing lists the file.
+pub async fn verify(url: &str, sha256: &str, token: Option<&str>) -> Option<ChecksumCheck> {
+    let file_name = github::release_asset_name(url)?;
+    let files = github::fetch_release_files(url, token, |name| is_checksum_asset(name, &file_name))
+        .await
+        .ok()?;
+    let Some(files) = files else {
+        return Some(ChecksumCheck {
+            source: "api.github.com".to_string(),
+            expected: None,
+            status: ChecksumStatus::Unverified,
+        });
+    };
+
+    let mut checks = files.iter().filter_map(|(asset, content)| {
+        let expected = expected_hash(content, asset, &file_name)?.to_ascii_uppercase();
+        let status = if expected.eq_ignore_ascii_case(sha256) {
+            ChecksumStatus::Match
+        } else {
+            ChecksumStatus::Mismatch
+        };
+        Some(ChecksumCheck {
+            source: asset.clone(),
+            expected: Some(expected),
+            status,
+        })
+    });
+    // A single disagreeing list is enough to distrust the download
+    let first = checks.next()?;
+    if first.status == ChecksumStatus::Mismatch {
+        return Some(first);
+    }
+    Some(checks.find(|c| c.status == ChecksumStatus::Mismatch).unwrap_or(first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn parses_gnu_lines() {
+        assert_eq!(parse_line(&format!("{}  setup.exe", HASH)), Some(("setup.exe", HASH)));
+        assert_eq!(parse_line(&format!("{} *setup.exe", HASH)), Some(("setup.exe", HASH)));
+        assert_eq!(parse_line(&format!("{}  ./dist/my setup.exe", HASH)), Some(("./dist/my setup.exe", HASH)));
+        assert_eq!(parse_line(&format!("\\{}  dir\\\\setup.exe", HASH)), Some(("dir\\\\setup.exe", HASH)));
+    }
+
+    #[test]
+    fn parses_bsd_lines() {
+        assert_eq!(parse_line(&format!("SHA256 (setup.exe) = {}", HASH)), Some(("setup.exe", HASH)));
+        assert_eq!(parse_line(&format!("SHA2-256(app (x64).msi)= {}", HASH)), Some(("app (x64).msi", HASH)));
+    }
+
+    #[test]
+    fn rejects_other_lines() {
+        assert_eq!(parse_line(""), None);
+        assert_eq!(parse_line("# checksums"), None);
+        assert_eq!(parse_line(HASH), None);
+        assert_eq!(parse_line(&format!("{}  setup.exe", &HASH[..40])), None);
+        assert_eq!(parse_line(&format!("SHA256 (setup.exe) {}", HASH)), None);
+    }
+
+    #[test]
+    fn finds_the_listed_hash() {
+        let list = format!("{}  other.exe\n{}  ./Setup.exe\n", "0".repeat(64), HASH);
+        assert_eq!(expected_hash(&list, "SHA256SUMS", "Setup.exe").as_deref(), Some(HASH));
+        assert_eq!(expected_hash(&list, "SHA256SUMS", "setup.exe").as_deref(), Some(HASH));
+        assert_eq!(expected_hash(HASH, "setup.exe.sha256", "setup.exe").as_deref(), Some(HASH));
+        assert_eq!(expected_hash(HASH, "SHA256SUMS", "setup.exe"), None);
+    }
+}
@@ -0,0 +1,457 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::msix::MsixDependency;
+use crate::pe::{self, read_at, PeHeader};
+
+/// Marks the placeholder in a .NET apphost that holds the offset of the single-file
+/// bundle header; the offset is the 8 bytes before it.
+const BUNDLE_SIGNATURE: [u8; 32] = [
+    0x8b, 0x12, 0x02, 0xb9, 0x6a, 0x61, 0x20, 0x38, 0x72, 0x7b, 0x93, 0x02, 0x14, 0xd7, 0xa0,
+    0x32, 0x13, 0xf5, 0xb9, 0xe6, 0xef, 0xae, 0x33, 0x18, 0xee, 0x3b, 0x2d, 0xce, 0x24, 0xb3,
+    0x6a, 0xae,
+];
+/// Sections larger than this are not searched for the bundle signature.
+const MAX_SCANNED_SECTION: u32 = 16 * 1024 * 1024;
+const MAX_RUNTIME_CONFIG: u64 = 1024 * 1024;
+const NET_CORE_MARKER: &[u8] = b".NETCoreApp,Version=v";
+
+/// Runtime DLLs and the redistributable providing them, with the minimum version
+/// that first shipped each DLL.
+const VC_RUNTIME_DLLS: &[(&str, &str, &str)] = &[
+    ("vcruntime140.dll", "2015+", "14.0"),
+    ("vcruntime140_1.dll", "2015+", "14.20"),
+    ("msvcp140.dll", "2015+", "14.0"),
+    ("msvcp140_1.dll", "2015+", "14.10"),
+    ("msvcp140_2.dll", "2015+", "14.14"),
+    ("msvcp140_atomic_wait.dll", "2015+", "14.28"),
+    ("msvcp140_codecvt_ids.dll", "2015+", "14.28"),
+    ("concrt140.dll", "2015+", "14.0"),
+    ("vccorlib140.dll", "2015+", "14.0"),
+    ("msvcr120.dll", "2013", "12.0"),
+    ("msvcp120.dll", "2013", "12.0"),
+    ("msvcr110.dll", "2012", "11.0"),
+    ("msvcp110.dll", "2012", "11.0"),
+    ("msvcr100.dll", "2010", "10.0"),
+    ("msvcp100.dll", "2010", "10.0"),
+];
+/// Legacy DirectX SDK components that are not part of Windows.
+const DIRECTX_DLL_PREFIXES: &[&str] = &["d3dx9_", "d3dx10", "d3dx11", "xinput1_3", "xaudio2_7", "x3daudio1_", "xactengine"];
+
+/// A package winget should install first, with why it was suggested.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencySuggestion {
+    pub package_identifier: String,
+    pub minimum_version: Option<String>,
+    /// E.g. `imports vcruntime140.dll` or `bundles VC_redist.x64.exe`.
+    pub reason: String,
+}
+
+fn suggestion(package_identifier: String, minimum_version: Option<&str>, reason: String) -> DependencySuggestion {
+    DependencySuggestion {
+        package_identifier,
+        minimum_version: minimum_version.map(|v| v.to_string()),
+        reason,
+    }
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version.split('.').map(|p| p.parse().unwrap_or(0)).collect()
+}
+
+/// Adds suggestions, keeping one per package with the highest minimum version.
+pub fn merge(into: &mut Vec<DependencySuggestion>, more: Vec<DependencySuggestion>) {
+    for dep in more {
+        match into.iter_mut().find(|d| d.package_identifier == dep.package_identifier) {
+            Some(existing) => {
+                let higher = match (&existing.minimum_version, &dep.minimum_version) {
+                    (Some(a), Some(b)) => version_parts(b) > version_parts(a),
+                    (None, Some(_)) => true,
+                    _ => false,
+                };
+                if higher {
+                    *existing = dep;
+                }
+            }
+            None => into.push(dep),
+        }
+    }
+}
+
+/// Suggestions for the runtime DLLs an image imports. DLLs in `shipped` are deployed
+/// app-locally and need nothing installed.
+pub fn from_imports(imports: &[String], arch: Option<&str>, shipped: &[String]) -> Vec<DependencySuggestion> {
+    let mut deps = Vec::new();
+    for dll in imports.iter().filter(|d| !shipped.contains(d)) {
+        let found = if let Some((_, year, version)) = VC_RUNTIME_DLLS.iter().find(|(name, _, _)| name == dll) {
+            // The ARM64 redistributable only exists for 2015+
+            let arch = arch.filter(|a| *a != "arm64" || *year == "2015+");
+            arch.map(|arch| (format!("Microsoft.VCRedist.{}.{}", year, arch), Some(*version)))
+        } else if DIRECTX_DLL_PREFIXES.iter().any(|p| dll.starts_with(p)) {
+            Some(("Microsoft.DirectX".to_string(), None))
+        } else if dll == "webview2loader.dll" {
+            Some(("Microsoft.EdgeWebView2Runtime".to_string(), None))
+        } else {
+            None
+        };
+        if let Some((id, version)) = found {
+            merge(&mut deps, vec![suggestion(id, version, format!("imports {}", dll))]);
+        }
+    }
+    deps
+}
+
+/// Version key used in .NET package identifiers: `8` for 8.0, `3_1` for 3.1.
+fn dotnet_channel(version: &str) -> Option<String> {
+    let mut parts = version.split('.');
+    let major: u32 = parts.next()?.parse().ok()?;
+    if major >= 5 {
+        Some(major.to_string())
+    } else {
+        Some(format!("{}_{}", major, parts.next()?))
+    }
+}
+
+fn dotnet_package(framework: &str, version: &str) -> Option<String> {
+    let kind = match framework {
+        "Microsoft.WindowsDesktop.App" => "DesktopRuntime",
+        "Microsoft.NETCore.App" => "Runtime",
+        "Microsoft.AspNetCore.App" => "AspNetCore",
+        _ => return None,
+    };
+    Some(format!("Microsoft.DotNet.{}.{}", kind, dotnet_channel(version)?))
+}
+
+/// Shared frameworks a `*.runtimeconfig.json` requires; self-contained apps list none.
+pub fn from_runtime_config(json: &str, source: &str) -> Vec<DependencySuggestion> {
+    let Ok(config) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Vec::new();
+    };
+    let options = &config["runtimeOptions"];
+    let frameworks = options["frameworks"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .chain(options.get("framework").cloned());
+
+    let mut deps = Vec::new();
+    for framework in frameworks {
+        let (Some(name), Some(version)) = (framework["name"].as_str(), framework["version"].as_str()) else {
+            continue;
+        };
+        if let Some(id) = dotnet_package(name, version) {
+            merge(&mut deps, vec![suggestion(id, Some(version), format!("{} requires {}", source, name))]);
+        }
+    }
+    // The desktop and ASP.NET frameworks include the base runtime
+    if deps.iter().any(|d| !d.package_identifier.starts_with("Microsoft.DotNet.Runtime.")) {
+        deps.retain(|d| !d.package_identifier.starts_with("Microsoft.DotNet.Runtime."));
+    }
+    deps
+}
+
+/// Target framework recorded in a managed image's metadata.
+fn from_clr_metadata(metadata: &[u8]) -> Option<DependencySuggestion> {
+    let start = metadata
+        .windows(NET_CORE_MARKER.len())
+        .position(|w| w == NET_CORE_MARKER)?
+        + NET_CORE_MARKER.len();
+    let version: String = metadata[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b'.')
+        .map(|&b| b as char)
+        .collect();
+    let contains = |needle: &[u8]| metadata.windows(needle.len()).any(|w| w == needle);
+    let framework = if contains(b"System.Windows.Forms") || contains(b"PresentationFramework") {
+        "Microsoft.WindowsDesktop.App"
+    } else {
+        "Microsoft.NETCore.App"
+    };
+    let id = dotnet_package(framework, &version)?;
+    Some(suggestion(id, Some(&version), format!("targets .NETCoreApp {}", version)))
+}
+
+/// Reads a length-prefixed string of a bundle header, returning the offset after it.
+fn skip_bundle_string(header: &[u8], offset: usize) -> Option<usize> {
+    let (mut len, mut shift, mut pos) = (0usize, 0, offset);
+    loop {
+        let byte = *header.get(pos)?;
+        len |= ((byte & 0x7f) as usize) << shift;
+        pos += 1;
+        if byte & 0x80 == 0 {
+            return Some(pos + len);
+        }
+        shift += 7;
+        if shift > 28 {
+            return None;
+        }
+    }
+}
+
+fn le_u64(buf: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+/// The `runtimeconfig.json` embedded in a .NET single-file bundle.
+fn bundled_runtime_config<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<String> {
+    let bundle_offset = header
+        .sections
+        .iter()
+        .filter(|s| s.raw_size <= MAX_SCANNED_SECTION)
+        .find_map(|s| {
+            let data = read_at(reader, s.raw_offset as u64, s.raw_size as usize)?;
+            let at = data.windows(BUNDLE_SIGNATURE.len()).position(|w| w == BUNDLE_SIGNATURE)?;
+            le_u64(&data, at.checked_sub(8)?)
+        })
+        .filter(|&offset| offset != 0)?;
+
+    let bundle = read_at(reader, bundle_offset, 512)?;
+    // Version 1 bundles (.NET Core 3) do not locate the config in the header
+    if pe::le_u32(&bundle, 0)? < 2 {
+        return None;
+    }
+    let after_id = skip_bundle_string(&bundle, 12)?;
+    let config_offset = le_u64(&bundle, after_id + 16)?;
+    let config_size = le_u64(&bundle, after_id + 24)?;
+    if config_offset == 0 || config_size > MAX_RUNTIME_CONFIG {
+        return None;
+    }
+    let config = read_at(reader, config_offset, config_size as usize)?;
+    Some(String::from_utf8_lossy(&config).to_string())
+}
+
+/// Package a redistributable installer file stands for. Only the names Microsoft ships
+/// them under count, so an app's own `WebView2Sample.exe` stays an installer candidate.
+pub fn from_file_name(name: &str, arch: Option<&str>) -> Option<DependencySuggestion> {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let lower = file.to_ascii_lowercase();
+    let reason = format!("bundles {}", file);
+    let named_arch = ["arm64", "x64", "x86"].into_iter().find(|a| lower.contains(a));
+
+    for (prefix, framework) in [
+        ("windowsdesktop-runtime-", "Microsoft.WindowsDesktop.App"),
+        ("aspnetcore-runtime-", "Microsoft.AspNetCore.App"),
+        ("dotnet-runtime-", "Microsoft.NETCore.App"),
+    ] {
+        if let Some(rest) = lower.strip_prefix(prefix) {
+            let version: String = rest.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+            let version = version.trim_end_matches('.');
+            let id = dotnet_package(framework, version)?;
+            return Some(suggestion(id, Some(version), reason));
+        }
+    }
+
+    if lower.starts_with("vc_redist") || lower.starts_with("vcredist") {
+        // Build numbers such as 14.29.30100 contain the short forms too, so only whole
+        // segments count; a year may also be glued to the name (`vcredist2013_x64`)
+        let segments: Vec<&str> = lower.split(['.', '_', '-', ' ']).collect();
+        let has_year = |year: &str| {
+            segments
+                .iter()
+                .any(|s| s.trim_start_matches(|c: char| c.is_ascii_alphabetic()) == year)
+        };
+        let has_segment = |short: &str| segments.contains(&short);
+        let year = if ["2015", "2017", "2019", "2022"].into_iter().any(has_year) {
+            "2015+"
+        } else if has_year("2013") {
+            "2013"
+        } else if has_year("2012") {
+            "2012"
+        } else if has_year("2010") {
+            "2010"
+        } else if lower.starts_with("vc_redist") || has_segment("140") {
+            // `VC_redist` is the 2015+ naming
+            "2015+"
+        } else if has_segment("120") {
+            "2013"
+        } else if has_segment("110") {
+            "2012"
+        } else if has_segment("100") {
+            "2010"
+        } else {
+            // Older `vcredist_x64.exe` files do not say which release they are
+            return None;
+        };
+        let arch = named_arch.or(arch)?;
+        return Some(suggestion(format!("Microsoft.VCRedist.{}.{}", year, arch), None, reason));
+    }
+    let is_exe = lower.ends_with(".exe");
+    // The bootstrapper, or the standalone installer with its architecture in the name
+    if lower == "microsoftedgewebview2setup.exe"
+        || (is_exe && lower.starts_with("microsoftedgewebview2runtimeinstaller"))
+    {
+        return Some(suggestion("Microsoft.EdgeWebView2Runtime".to_string(), None, reason));
+    }
+    // The web installer, or the setup of the offline DirectX redistributable
+    if lower == "dxwebsetup.exe" || lower == "dxsetup.exe" {
+        return Some(suggestion("Microsoft.DirectX".to_string(), None, reason));
+    }
+    None
+}
+
+/// Maps framework packages an MSIX declares to their winget packages.
+pub fn from_msix(dependencies: &[MsixDependency]) -> Vec<DependencySuggestion> {
+    let mut deps = Vec::new();
+    for dep in dependencies {
+        let reason = format!("declares PackageDependency {}", dep.name);
+        let found = if dep.name == "Microsoft.VCLibs.140.00.UWPDesktop" {
+            Some(suggestion("Microsoft.VCLibs.Desktop.14".to_string(), dep.min_version.as_deref(), reason))
+        } else if dep.name.starts_with("Microsoft.UI.Xaml.") {
+            Some(suggestion(dep.name.clone(), dep.min_version.as_deref(), reason))
+        } else if dep.name.starts_with("Microsoft.WindowsAppRuntime.") {
+            // Framework package versions do not follow the winget package versions
+            Some(suggestion(dep.name.clone(), None, reason))
+        } else {
+            None
+        };
+        if let Some(found) = found {
+            merge(&mut deps, vec![found]);
+        }
+    }
+    deps
+}
+
+/// Everything an executable reveals about the runtimes it needs: its imports, its CLR
+/// target and a bundled `runtimeconfig.json`. Redistributables a Burn bundle chains are
+/// installed by the bundle itself, so they are not suggested.
+/// `shipped` lists the DLLs found next to it, lowercased.
+pub fn from_pe<R: Read + Seek>(reader: &mut R, header: &PeHeader, shipped: &[String]) -> Vec<DependencySuggestion> {
+    // Managed and 32-bit ARM images have no matching redistributable
+    let arch = pe::architecture(reader, header)
+        .map(|(arch, _)| arch)
+        .filter(|arch| matches!(*arch, "x64" | "x86" | "arm64"));
+    let mut deps = from_imports(&pe::imported_dlls(reader, header), arch, shipped);
+    if let Some(dep) = pe::clr_metadata(reader, header).and_then(|m| from_clr_metadata(&m)) {
+        merge(&mut deps, vec![dep]);
+    }
+    if let Some(config) = bundled_runtime_config(reader, header) {
+        merge(&mut deps, from_runtime_config(&config, "bundled runtimeconfig.json"));
+    }
+    deps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vc_redist(name: &str) -> Option<String> {
+        from_file_name(name, Some("x64")).map(|d| d.package_identifier)
+    }
+
+    #[test]
+    fn build_numbers_do_not_look_like_old_runtimes() {
+        assert_eq!(vc_redist("VC_redist.x64_14.29.30100.exe").as_deref(), Some("Microsoft.VCRedist.2015+.x64"));
+        assert_eq!(vc_redist("vc_redist.x86_14.0.24120.exe").as_deref(), Some("Microsoft.VCRedist.2015+.x86"));
+    }
+
+    #[test]
+    fn years_and_short_versions_pick_the_release() {
+        assert_eq!(vc_redist("vcredist_2013_x64.exe").as_deref(), Some("Microsoft.VCRedist.2013.x64"));
+        assert_eq!(vc_redist("vcredist2012_x86.exe").as_deref(), Some("Microsoft.VCRedist.2012.x86"));
+        assert_eq!(vc_redist("vcredist-100-x64.exe").as_deref(), Some("Microsoft.VCRedist.2010.x64"));
+        assert_eq!(vc_redist("vcredist_x64.exe"), None);
+    }
+
+    fn redist(name: &str) -> Option<String> {
+        from_file_name(name, None).map(|d| d.package_identifier)
+    }
+
+    #[test]
+    fn only_redistributable_names_count() {
+        assert_eq!(redist("redist/MicrosoftEdgeWebview2Setup.exe").as_deref(), Some("Microsoft.EdgeWebView2Runtime"));
+        assert_eq!(
+            redist("MicrosoftEdgeWebView2RuntimeInstallerX64.exe").as_deref(),
+            Some("Microsoft.EdgeWebView2Runtime")
+        );
+        assert_eq!(redist("DirectX\\DXSETUP.exe").as_deref(), Some("Microsoft.DirectX"));
+        assert_eq!(redist("dxwebsetup.exe").as_deref(), Some("Microsoft.DirectX"));
+        assert_eq!(
+            redist("windowsdesktop-runtime-8.0.1-win-x64.exe").as_deref(),
+            Some("Microsoft.DotNet.DesktopRuntime.8")
+        );
+        assert_eq!(redist("VC_redist.arm64.exe").as_deref(), Some("Microsoft.VCRedist.2015+.arm64"));
+
+        assert_eq!(redist("WebView2Sample.exe"), None);
+        assert_eq!(redist("MyDirectXTool.exe"), None);
+        assert_eq!(redist("MicrosoftEdgeWebview2Setup.exe.config"), None);
+        assert_eq!(redist("my-vcredist-helper-x64.exe"), None);
+        assert_eq!(redist("app-dotnet-runtime-8.0.1.exe"), None);
+        // Without an architecture in its name, a VC redist cannot be placed
+        assert_eq!(redist("VC_redist.exe"), None);
+    }
+
+    fn ids(deps: &[DependencySuggestion]) -> Vec<(&str, Option<&str>)> {
+        deps.iter()
+            .map(|d| (d.package_identifier.as_str(), d.minimum_version.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn imports_map_to_runtimes() {
+        let imports: Vec<String> = ["kernel32.dll", "vcruntime140.dll", "vcruntime140_1.dll", "msvcp140.dll", "d3dx9_43.dll", "webview2loader.dll"]
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        let deps = from_imports(&imports, Some("x64"), &[]);
+        assert_eq!(
+            ids(&deps),
+            [
+                ("Microsoft.VCRedist.2015+.x64", Some("14.20")),
+                ("Microsoft.DirectX", None),
+                ("Microsoft.EdgeWebView2Runtime", None),
+            ]
+        );
+        assert_eq!(deps[0].reason, "imports vcruntime140_1.dll");
+
+        // App-local copies need nothing installed
+        let shipped = vec!["vcruntime140.dll".to_string(), "vcruntime140_1.dll".to_string(), "msvcp140.dll".to_string()];
+        assert_eq!(ids(&from_imports(&imports[..4], Some("x64"), &shipped)), []);
+        // No ARM64 redistributable before 2015, and none without an architecture
+        let old = vec!["msvcr120.dll".to_string()];
+        assert_eq!(ids(&from_imports(&old, Some("arm64"), &[])), []);
+        assert_eq!(ids(&from_imports(&old, Some("x86"), &[])), [("Microsoft.VCRedist.2013.x86", Some("12.0"))]);
+        assert_eq!(ids(&from_imports(&imports[1..2], None, &[])), []);
+    }
+
+    #[test]
+    fn runtime_config_lists_shared_frameworks() {
+        let desktop = r#"{"runtimeOptions": {"tfm": "net8.0", "frameworks": [
+            {"name": "Microsoft.NETCore.App", "version": "8.0.1"},
+            {"name": "Microsoft.WindowsDesktop.App", "version": "8.0.1"}
+        ]}}"#;
+        let deps = from_runtime_config(desktop, "app.runtimeconfig.json");
+        assert_eq!(ids(&deps), [("Microsoft.DotNet.DesktopRuntime.8", Some("8.0.1"))]);
+        assert_eq!(deps[0].reason, "app.runtimeconfig.json requires Microsoft.WindowsDesktop.App");
+
+        let console = r#"{"runtimeOptions": {"framework": {"name": "Microsoft.NETCore.App", "version": "3.1.0"}}}"#;
+        assert_eq!(ids(&from_runtime_config(console, "x")), [("Microsoft.DotNet.Runtime.3_1", Some("3.1.0"))]);
+
+        let self_contained = r#"{"runtimeOptions": {"includedFrameworks": [{"name": "Microsoft.NETCore.App", "version": "8.0.1"}]}}"#;
+        assert_eq!(ids(&from_runtime_config(self_contained, "x")), []);
+        assert_eq!(ids(&from_runtime_config("not json", "x")), []);
+    }
+
+    #[test]
+    fn msix_framework_packages_map_to_winget_packages() {
+        let dependency = |name: &str, min_version: Option<&str>| MsixDependency {
+            name: name.to_string(),
+            min_version: min_version.map(|v| v.to_string()),
+        };
+        let deps = from_msix(&[
+            dependency("Microsoft.VCLibs.140.00.UWPDesktop", Some("14.0.30704.0")),
+            dependency("Microsoft.UI.Xaml.2.8", Some("8.2310.30001.0")),
+            dependency("Microsoft.WindowsAppRuntime.1.5", Some("5001.70.1338.0")),
+            dependency("Contoso.Framework", Some("1.0.0.0")),
+        ]);
+        assert_eq!(
+            ids(&deps),
+            [
+                ("Microsoft.VCLibs.Desktop.14", Some("14.0.30704.0")),
+                ("Microsoft.UI.Xaml.2.8", Some("8.2310.30001.0")),
+                ("Microsoft.WindowsAppRuntime.1.5", None),
+            ]
+        );
+    }
+}
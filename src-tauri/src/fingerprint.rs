@@ -0,0 +1,213 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::pe::{self, PeHeader};
+use crate::version_info::VersionInfo;
+
+/// How much of the image (headers, sections, resources) is scanned for markers.
+const MAX_IMAGE_SCAN: u64 = 16 * 1024 * 1024;
+/// How much of the overlay appended after the last section is scanned.
+const MAX_OVERLAY_SCAN: usize = 256 * 1024;
+/// Words that give an executable away as an installer when it names or describes itself.
+const SETUP_WORDS: &[&str] = &["setup", "install", "bootstrap"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Technology {
+    Inno,
+    Nullsoft,
+    Burn,
+    InstallShield,
+    Squirrel,
+    AdvancedInstaller,
+}
+
+impl Technology {
+    pub fn id(self) -> &'static str {
+        match self {
+            Technology::Inno => "inno",
+            Technology::Nullsoft => "nullsoft",
+            Technology::Burn => "burn",
+            Technology::InstallShield => "installShield",
+            Technology::Squirrel => "squirrel",
+            Technology::AdvancedInstaller => "advancedInstaller",
+        }
+    }
+
+    /// The winget `InstallerType` for installers built with this technology.
+    pub fn installer_type(self) -> &'static str {
+        match self {
+            Technology::Inno => "inno",
+            Technology::Nullsoft => "nullsoft",
+            Technology::Burn => "burn",
+            Technology::InstallShield | Technology::Squirrel | Technology::AdvancedInstaller => {
+                "exe"
+            }
+        }
+    }
+}
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Looks for a marker stored either as ASCII or as UTF-16LE (version resources, manifests).
+fn contains_text(haystack: &[u8], text: &str) -> bool {
+    contains(haystack, text.as_bytes()) || contains(haystack, &utf16le(text))
+}
+
+/// File offset where the appended data (installer payload) begins.
+pub fn overlay_offset(header: &PeHeader) -> u64 {
+    header
+        .sections
+        .iter()
+        .map(|s| s.raw_offset as u64 + s.raw_size as u64)
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn detect<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<Technology> {
+    if header.sections.iter().any(|s| s.name == ".wixburn") {
+        return Some(Technology::Burn);
+    }
+
+    let overlay_start = overlay_offset(header);
+    let image = pe::read_at(reader, 0, overlay_start.min(MAX_IMAGE_SCAN) as usize)?;
+    let overlay = read_overlay(reader, overlay_start);
+
+    // Inno: setup loader offset table in the resources, setup data id in the payload
+    if contains(&image, b"rDlPtS")
+        || contains(&overlay, b"Inno Setup Setup Data")
+        || contains_text(&image, "This installation was built with Inno Setup")
+    {
+        return Some(Technology::Inno);
+    }
+
+    // NSIS: first header of the appended data, or the exehead manifest identity
+    if contains(&overlay, b"NullsoftInst") || contains(&image, b"Nullsoft.NSIS.exehead") {
+        return Some(Technology::Nullsoft);
+    }
+
+    if contains_text(&image, "InstallShield") || contains(&overlay, b"ISSetupStream") {
+        return Some(Technology::InstallShield);
+    }
+
+    if contains_text(&image, "SquirrelSetup") || contains_text(&image, "Squirrel.Windows") {
+        return Some(Technology::Squirrel);
+    }
+
+    if contains_text(&image, "Advanced Installer") || contains_text(&image, "Caphyon") {
+        return Some(Technology::AdvancedInstaller);
+    }
+
+    None
+}
+
+pub fn looks_like_setup(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    SETUP_WORDS.iter().any(|w| lower.contains(w))
+}
+
+/// Whether an executable no known framework built is the application itself rather
+/// than an installer: a GUI or console image that does not call itself a setup, does
+/// not ask for elevation and carries no large appended payload.
+pub fn is_portable<R: Read + Seek>(
+    reader: &mut R,
+    header: &PeHeader,
+    file_name: &str,
+    version_info: Option<&VersionInfo>,
+) -> bool {
+    if header.characteristics & pe::IMAGE_FILE_DLL != 0
+        || !matches!(header.subsystem, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI | pe::IMAGE_SUBSYSTEM_WINDOWS_CUI)
+    {
+        return false;
+    }
+
+    let described_as_setup = version_info.is_some_and(|info| {
+        [&info.original_filename, &info.file_description]
+            .into_iter()
+            .flatten()
+            .any(|text| looks_like_setup(text))
+    });
+    if looks_like_setup(file_name) || described_as_setup {
+        return false;
+    }
+
+    let requires_admin = pe::find_resources(reader, header, pe::RT_MANIFEST)
+        .first()
+        .and_then(|r| pe::read_resource(reader, r))
+        .is_some_and(|manifest| contains(&manifest, b"requireAdministrator"));
+    if requires_admin {
+        return false;
+    }
+
+    // The Authenticode certificate table also sits after the last section
+    let Ok(file_len) = reader.seek(SeekFrom::End(0)) else {
+        return false;
+    };
+    let certificate = header
+        .data_directory(pe::IMAGE_DIRECTORY_ENTRY_SECURITY)
+        .map_or(0, |d| d.size as u64);
+    let payload = file_len.saturating_sub(overlay_offset(header) + certificate);
+    payload * 4 <= file_len
+}
+
+fn read_overlay<R: Read + Seek>(reader: &mut R, overlay_start: u64) -> Vec<u8> {
+    let mut overlay = Vec::new();
+    if reader.seek(SeekFrom::Start(overlay_start)).is_ok() {
+        let _ = reader
+            .by_ref()
+            .take(MAX_OVERLAY_SCAN as u64)
+            .read_to_end(&mut overlay);
+    }
+    overlay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pe::tests::image;
+    use std::io::Cursor;
+
+    fn portable(data: &[u8], file_name: &str, version_info: Option<&VersionInfo>) -> bool {
+        let mut reader = Cursor::new(data);
+        let header = pe::read_header(&mut reader).unwrap();
+        is_portable(&mut reader, &header, file_name, version_info)
+    }
+
+    #[test]
+    fn applications_are_portable() {
+        let tool = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_CUI, &[]);
+        assert!(portable(&tool, "rg.exe", None));
+        let app = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[(pe::RT_MANIFEST, 1, b"<requestedExecutionLevel level=\"asInvoker\"/>")]);
+        assert!(portable(&app, "Editor.exe", None));
+        // Small appended data, such as a signature or a license, is not a payload
+        let mut with_tail = app.clone();
+        with_tail.extend_from_slice(&[0; 64]);
+        assert!(portable(&with_tail, "Editor.exe", None));
+    }
+
+    #[test]
+    fn installers_and_libraries_are_not_portable() {
+        let gui = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[]);
+        let mut with_payload = gui.clone();
+        with_payload.extend(std::iter::repeat_n(0x5a, gui.len() * 2));
+        assert!(!portable(&with_payload, "Editor.exe", None));
+
+        let library = image(0x0022 | pe::IMAGE_FILE_DLL, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[]);
+        assert!(!portable(&library, "editor.dll", None));
+        let driver = image(0x0022, 1, &[]);
+        assert!(!portable(&driver, "editor.sys", None));
+
+        assert!(!portable(&gui, "Editor-Setup.exe", None));
+        let described = VersionInfo {
+            file_description: Some("Editor Installer".to_string()),
+            ..Default::default()
+        };
+        assert!(!portable(&gui, "Editor.exe", Some(&described)));
+        let elevated = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[(pe::RT_MANIFEST, 1, b"<requestedExecutionLevel level=\"requireAdministrator\"/>")]);
+        assert!(!portable(&elevated, "Editor.exe", None));
+    }
+}
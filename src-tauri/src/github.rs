@@ -177,6 +177,8 @@ struct GitHubLicense {
 struct GitHubReleaseAsset {
     name: String,
     browser_download_url: String,
+    #[serde(default)]
+    size: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -302,6 +304,85 @@ fn parse_github_url(url: &str) -> Option<(String, String, Option<String>)> {
     Some((owner, repo, tag))
 }
 
+/// Text assets larger than this are not fetched alongside a release download.
+const MAX_RELEASE_TEXT_ASSET: u64 = 1024 * 1024;
+
+/// Asset name of a `github.com/owner/repo/releases/download/tag/asset` URL.
+pub fn release_asset_name(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let segments: Vec<&str> = parsed.path_segments()?.collect();
+    match segments.as_slice() {
+        [_, _, "releases", "download", _, _] if parsed.host_str() == Some("github.com") => {
+            crate::hash::file_name_from_url(&parsed)
+        }
+        _ => None,
+    }
+}
+
+/// Downloads the small text assets selected by `wanted` from the release a download URL
+/// belongs to, as `(name, content)` pairs. `None` when the API refuses the request,
+/// usually because the anonymous rate limit is used up.
+pub async fn fetch_release_files(
+    url: &str,
+    token: Option<&str>,
+    wanted: impl Fn(&str) -> bool,
+) -> Result<Option<Vec<(String, String)>>, String> {
+    release_asset_name(url).ok_or_else(|| "Not a GitHub release download".to_string())?;
+    let (owner, repo, tag) = parse_github_url(url)
+        .ok_or_else(|| "Not a GitHub URL".to_string())?;
+    let tag = tag.ok_or_else(|| "No release tag in URL".to_string())?;
+
+    let client = reqwest::Client::builder()
+        .connect_timeout(crate::hash::CONNECT_TIMEOUT)
+        .read_timeout(crate::hash::READ_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+    let response = client
+        .get(format!(
+            "https://api.github.com/repos/{}/{}/releases/tags/{}",
+            owner, repo, tag
+        ))
+        .headers(build_headers_optional(token.filter(|t| !t.trim().is_empty())))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if matches!(
+        response.status(),
+        reqwest::StatusCode::FORBIDDEN | reqwest::StatusCode::TOO_MANY_REQUESTS
+    ) {
+        return Ok(None);
+    }
+    let release: GitHubRelease = response
+        .error_for_status()
+        .map_err(|e| format!("Release not found: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let mut files = Vec::new();
+    for asset in release.assets.iter().filter(|a| wanted(&a.name)) {
+        if asset.size > MAX_RELEASE_TEXT_ASSET {
+            continue;
+        }
+        // One unreachable asset should not hide the others
+        let Ok(response) = client
+            .get(&asset.browser_download_url)
+            .header(USER_AGENT, "UniCreate/1.0")
+            .send()
+            .await
+        else {
+            continue;
+        };
+        if !response.status().is_success() {
+            continue;
+        }
+        if let Ok(content) = response.text().await {
+            files.push((asset.name.clone(), content));
+        }
+    }
+    Ok(Some(files))
+}
+
 /// Clean version string: remove 'v' prefix, etc.
 fn clean_version(tag: &str) -> String {
     let v = tag.strip_prefix('v').unwrap_or(tag);
@@ -1,26 +1,217 @@
 use reqwest;
-use serde::Serialize;
+use reqwest::header::{
+    CONTENT_RANGE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use futures_util::StreamExt;
+use tokio_util::sync::CancellationToken;
 
-#[derive(Debug, Serialize)]
+use crate::archive::{self, ArchiveInfo};
+use crate::authenticode::{self, SignatureInfo};
+use crate::burn::{self, BurnInfo};
+use crate::cache;
+use crate::checksums::{self, ChecksumCheck};
+use crate::deps::{self, DependencySuggestion};
+use crate::fingerprint;
+use crate::icons::{self, IconInfo};
+use crate::inno::{self, InnoInfo};
+use crate::msi::{self, MsiInfo};
+use crate::msix::{self, MsixInfo};
+use crate::pe;
+use crate::sparse::SparseFile;
+use crate::version_info::{self, VersionInfo};
+use crate::yaml_generator::{InstallationMetadata, InstallerEntry, InstallerSwitches};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct HashResult {
     pub sha256: String,
     pub file_size: u64,
     pub file_name: String,
     pub detected_type: Option<String>,
+    /// Installer framework recognized from the executable's markers, when known.
+    pub installer_technology: Option<String>,
     pub detected_arch: Option<String>,
+    /// Where `detected_arch` came from: `peHeader`, `clrHeader`, `msiTemplate`,
+    /// `appxManifest` or `fileName`.
+    pub arch_source: Option<String>,
     pub signature_sha256: Option<String>,
+    /// Authenticode signer of exe and msi installers; absent for other formats.
+    pub authenticode: Option<SignatureInfo>,
+    pub msi: Option<MsiInfo>,
+    pub msix: Option<MsixInfo>,
+    /// Setup header of Inno Setup installers.
+    #[serde(default)]
+    pub inno: Option<InnoInfo>,
+    /// Registration and chained MSIs of WiX Burn bundles.
+    #[serde(default)]
+    pub burn: Option<BurnInfo>,
+    /// Strings from the executable's version resource, used to pre-fill locale metadata.
+    pub version_info: Option<VersionInfo>,
+    /// Nested installer suggestion for `.zip` files.
+    pub archive: Option<ArchiveInfo>,
+    /// Application icons from the executable's resources or the package's logo assets.
+    #[serde(default)]
+    pub icons: Vec<IconInfo>,
+    /// Packages to list under `Dependencies`, from imports, bundled runtimes and manifests.
+    #[serde(default)]
+    pub dependencies: Vec<DependencySuggestion>,
+    /// `Commands` for portables: the executable's alias, those of a zip's nested portables,
+    /// or a package's execution aliases.
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// `Protocols` registered by an MSI or declared by a package.
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    /// `FileExtensions` associated by an MSI or declared by a package.
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    /// One entry per architecture for bundles; `installer_url` is only set for downloads.
+    pub suggested_installers: Vec<InstallerEntry>,
+    /// Every URL requested for a download, starting with the one given; empty for local files.
+    pub redirect_chain: Vec<String>,
+    /// Where the download was actually served from.
+    pub final_url: Option<String>,
+    /// Served from the analysis cache after the server confirmed the file is unchanged.
+    #[serde(default)]
+    pub from_cache: bool,
+    /// Comparison with the checksum files of the GitHub release the download came from.
+    #[serde(default)]
+    pub checksum: Option<ChecksumCheck>,
+}
+
+/// Redirects followed before a download is abandoned.
+const MAX_REDIRECTS: usize = 10;
+const MAX_ATTEMPTS: u32 = 5;
+/// Doubled after every failed attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Longest pause between two chunks before the connection counts as dropped.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// Bytes kept from the end of a streamed package: its central directory and, usually,
+/// the signature and manifest written just before it.
+const PACKAGE_TAIL_LEN: usize = 4 * 1024 * 1024;
+/// Smallest range requested when a package read falls outside the kept tail.
+const RANGE_CHUNK_LEN: u64 = 256 * 1024;
+const MAX_RANGE_REQUESTS: usize = 32;
+
+fn percent_decode(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Splits header parameters on `;`, keeping quoted strings (and their escapes) intact.
+fn split_header_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut parts = Vec::new();
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                current.push(c);
+                escaped = false;
+            }
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    for part in parts {
+        if let Some((key, value)) = part.split_once('=') {
+            params.push((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    params
+}
+
+/// Extracts the file name from a `Content-Disposition` header (RFC 6266), preferring
+/// the RFC 5987 `filename*` form over the plain one.
+fn content_disposition_file_name(header: &str) -> Option<String> {
+    let params = split_header_params(header);
+    let extended = params.iter().find(|(k, _)| k == "filename*").and_then(|(_, v)| {
+        let (charset, rest) = v.split_once('\'')?;
+        let (_, encoded) = rest.split_once('\'')?;
+        let bytes = percent_decode(encoded);
+        if charset.eq_ignore_ascii_case("iso-8859-1") {
+            Some(bytes.iter().map(|&b| b as char).collect())
+        } else {
+            Some(String::from_utf8_lossy(&bytes).to_string())
+        }
+    });
+    extended
+        .or_else(|| {
+            params
+                .iter()
+                .find(|(k, _)| k == "filename")
+                .map(|(_, v)| v.clone())
+        })
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// Last path segment of a URL, percent-decoded.
+pub fn file_name_from_url(url: &reqwest::Url) -> Option<String> {
+    let segment = url.path_segments()?.rev().find(|s| !s.is_empty())?;
+    let name = String::from_utf8_lossy(&percent_decode(segment)).to_string();
+    (!name.trim().is_empty()).then_some(name)
+}
+
+/// Strips any directory part a server may have put in the name.
+fn base_name(name: &str) -> String {
+    name.rsplit(['/', '\\']).next().unwrap_or(name).trim().to_string()
+}
+
+/// A name that is safe to join onto the temp directory.
+fn safe_temp_name(name: &str) -> String {
+    let cleaned: String = base_name(name)
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(120)
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.').trim();
+    if cleaned.is_empty() {
+        "download".to_string()
+    } else {
+        cleaned.to_string()
+    }
 }
 
 fn detect_installer_type(filename: &str) -> Option<String> {
     let lower = filename.to_lowercase();
     if lower.ends_with(".msi") {
         Some("msi".to_string())
-    } else if lower.ends_with(".msix") || lower.ends_with(".msixbundle") || lower.ends_with(".appx") {
+    } else if lower.ends_with(".msix") || lower.ends_with(".msixbundle") || lower.ends_with(".appx") || lower.ends_with(".appxbundle") {
         Some("msix".to_string())
     } else if lower.ends_with(".exe") {
         Some("exe".to_string())
@@ -31,115 +222,979 @@ fn detect_installer_type(filename: &str) -> Option<String> {
     }
 }
 
+/// Bytes inspected to recognize the format of a download.
+const SNIFF_LEN: usize = 512;
+
+const OLE_MAGIC: &[u8] = &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+/// Recognizes installer containers from their leading bytes.
+fn sniff_installer_type(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"MZ") {
+        Some("exe")
+    } else if head.starts_with(OLE_MAGIC) {
+        Some("msi")
+    } else if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        Some("zip")
+    } else {
+        None
+    }
+}
+
+/// Describes payloads that are documents rather than binaries, such as CDN error pages.
+fn text_payload_kind(head: &[u8]) -> Option<&'static str> {
+    if head.is_empty() || sniff_installer_type(head).is_some() {
+        return None;
+    }
+    let text = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    let start = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map(|i| &text[i..])
+        .unwrap_or_default();
+    let lower: Vec<u8> = start.iter().take(16).map(|b| b.to_ascii_lowercase()).collect();
+    if lower.starts_with(b"<!doctype html") || lower.starts_with(b"<html") || lower.starts_with(b"<head") {
+        return Some("an HTML page");
+    }
+    if lower.starts_with(b"<") {
+        return Some("an XML document");
+    }
+    // Binaries have NUL bytes early on; a cut-off multibyte character at the end is fine
+    let is_utf8 = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if is_utf8 && !head.contains(&0) {
+        return Some("a text document");
+    }
+    None
+}
+
+fn reject_text_payload(head: &[u8]) -> Result<(), String> {
+    match text_payload_kind(head) {
+        Some(kind) => Err(format!(
+            "The server returned {} instead of an installer; check that the URL points to the file itself",
+            kind
+        )),
+        None => Ok(()),
+    }
+}
+
 fn detect_architecture(filename: &str) -> Option<String> {
     let lower = filename.to_lowercase();
+    // "arm" alone is too common a substring ("armory", "charm") to match anywhere
+    let has_arm_token = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|t| matches!(t, "arm" | "armv7" | "armhf"));
     if lower.contains("arm64") || lower.contains("aarch64") {
         Some("arm64".to_string())
     } else if lower.contains("x64") || lower.contains("amd64") || lower.contains("win64") || lower.contains("x86_64") {
         Some("x64".to_string())
     } else if lower.contains("x86") || lower.contains("win32") || lower.contains("ia32") || lower.contains("i686") || lower.contains("i386") {
         Some("x86".to_string())
-    } else if lower.contains("arm") {
+    } else if has_arm_token {
         Some("arm".to_string())
     } else {
         None
     }
 }
 
-fn extract_msix_signature_hash(file_path: &Path) -> Option<String> {
-    let file = std::fs::File::open(file_path).ok()?;
-    let mut archive = zip::ZipArchive::new(file).ok()?;
-    let mut signature = archive.by_name("AppxSignature.p7x").ok()?;
-
-    let mut hasher = Sha256::new();
-    let mut buf = [0u8; 65536];
-    loop {
-        let n = signature.read(&mut buf).ok()?;
-        if n == 0 { break; }
-        hasher.update(&buf[..n]);
+/// Picks the architecture from the PE header when there is one, falling back to the file name.
+///
+/// A 32-bit header is only trusted when the name does not say otherwise: most
+/// installer stubs are x86 images that deploy an x64 or arm64 payload.
+fn resolve_architecture(
+    from_header: Option<(&'static str, bool)>,
+    file_name: &str,
+) -> (Option<String>, Option<String>) {
+    let from_name = detect_architecture(file_name);
+    match from_header {
+        Some(("x86", _)) if from_name.as_deref().is_some_and(|a| a != "x86") => {
+            (from_name, Some("fileName".to_string()))
+        }
+        Some((arch, from_clr)) => {
+            let source = if from_clr { "clrHeader" } else { "peHeader" };
+            (Some(arch.to_string()), Some(source.to_string()))
+        }
+        None => {
+            let source = from_name.as_ref().map(|_| "fileName".to_string());
+            (from_name, source)
+        }
     }
-    Some(format!("{:X}", hasher.finalize()))
 }
 
-pub async fn download_and_hash(url: String) -> Result<HashResult, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("User-Agent", "UniCreate/1.0")
-        .send()
-        .await
-        .map_err(|e| format!("Download failed: {}", e))?;
+/// Runs the content-based detection shared by downloaded and local installers.
+fn analyze_file(file_path: &Path, file_name: String, sha256: String, file_size: u64) -> HashResult {
+    let mut file = std::fs::File::open(file_path).ok();
+    let mut detected_type = detect_installer_type(&file_name).or_else(|| {
+        let head = pe::read_at(file.as_mut()?, 0, OLE_MAGIC.len())?;
+        match sniff_installer_type(&head)? {
+            "zip" if std::fs::File::open(file_path).is_ok_and(msix::is_package) => {
+                Some("msix".to_string())
+            }
+            kind => Some(kind.to_string()),
+        }
+    });
+    let pe_header = file.as_mut().and_then(pe::read_header);
 
-    if !response.status().is_success() {
-        return Err(format!("HTTP error: {}", response.status()));
+    let mut header_arch = None;
+    let mut installer_technology = None;
+    let mut authenticode = None;
+    let mut version_info = None;
+    let mut icons = Vec::new();
+    let mut dependencies = Vec::new();
+    let mut inno = None;
+    let mut burn = None;
+    if let (Some(file), Some(header)) = (file.as_mut(), pe_header.as_ref()) {
+        header_arch = pe::architecture(file, header);
+        authenticode = Some(authenticode::read_pe_signature(file, header));
+        version_info = version_info::read(file, header);
+        icons = icons::from_pe(file, header);
+        dependencies = deps::from_pe(file, header, &[]);
+        if let Some(tech) = fingerprint::detect(file, header) {
+            detected_type = Some(tech.installer_type().to_string());
+            installer_technology = Some(tech.id().to_string());
+            if tech == fingerprint::Technology::Inno {
+                inno = inno::analyze(file, header);
+            }
+            if tech == fingerprint::Technology::Burn {
+                burn = burn::analyze(file, header);
+            }
+        } else if detected_type.as_deref() == Some("exe")
+            && fingerprint::is_portable(file, header, &file_name, version_info.as_ref())
+        {
+            detected_type = Some("portable".to_string());
+        }
     }
+    drop(file);
 
-    let file_name = response
-        .headers()
-        .get("content-disposition")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| {
-            s.split("filename=")
-                .nth(1)
-                .map(|f| f.trim_matches('"').to_string())
-        })
-        .unwrap_or_else(|| {
-            Path::new(url.split('?').next().unwrap_or(&url))
-                .file_name()
-                .map(|f| f.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string())
-        });
-
-    let detected_type = detect_installer_type(&file_name);
-    let detected_arch = detect_architecture(&file_name);
+    let (mut detected_arch, mut arch_source) = resolve_architecture(header_arch, &file_name);
     let is_msix = matches!(detected_type.as_deref(), Some("msix"));
 
-    // For MSIX, save to temp file for signature extraction
-    let temp_path = if is_msix {
-        Some(std::env::temp_dir().join(format!("unicreate_{}", &file_name)))
+    let is_msi = matches!(detected_type.as_deref(), Some("msi"));
+    let msi = if is_msi {
+        std::fs::File::open(file_path).ok().and_then(msi::analyze)
     } else {
         None
     };
-    let mut temp_file = match &temp_path {
-        Some(path) => Some(std::fs::File::create(path).map_err(|e| format!("Temp file error: {}", e))?),
-        None => None,
-    };
-
-    let mut hasher = Sha256::new();
-    let mut file_size: u64 = 0;
-
-    let mut stream = response.bytes_stream();
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("Stream error: {}", e))?;
-        file_size += chunk.len() as u64;
-        hasher.update(&chunk);
-        if let Some(ref mut f) = temp_file {
-            f.write_all(&chunk).map_err(|e| format!("Write error: {}", e))?;
-        }
+    if is_msi {
+        authenticode = std::fs::File::open(file_path).ok().and_then(msi::signature);
+    }
+    if let Some(arch) = msi.as_ref().and_then(|m| m.architecture.clone()) {
+        detected_arch = Some(arch);
+        arch_source = Some("msiTemplate".to_string());
     }
 
-    let hash = format!("{:X}", hasher.finalize());
+    let (signature_sha256, msix, package_icons) = if is_msix {
+        std::fs::File::open(file_path)
+            .map(msix::analyze)
+            .unwrap_or((None, None, Vec::new()))
+    } else {
+        (None, None, Vec::new())
+    };
 
-    let signature_sha256 = if let Some(ref path) = temp_path {
-        drop(temp_file);
-        let sig = extract_msix_signature_hash(path);
-        let _ = std::fs::remove_file(path);
-        sig
+    let mut archive = if matches!(detected_type.as_deref(), Some("zip")) {
+        std::fs::File::open(file_path).ok().and_then(archive::analyze)
     } else {
         None
     };
+    if let Some(ref mut archive) = archive {
+        deps::merge(&mut dependencies, std::mem::take(&mut archive.dependencies));
+    }
 
-    Ok(HashResult {
-        sha256: hash,
+    // winget links a portable under its first command
+    let commands = match (detected_type.as_deref(), &archive) {
+        (Some("portable"), _) => {
+            let built_as = version_info.as_ref().and_then(|v| v.original_filename.as_deref());
+            let name = built_as.filter(|n| n.to_ascii_lowercase().ends_with(".exe")).unwrap_or(&file_name);
+            vec![archive::command_alias(name)]
+        }
+        (_, Some(archive)) => archive
+            .nested_installer_files
+            .iter()
+            .filter_map(|f| f.portable_command_alias.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut result = HashResult {
+        sha256,
         file_size,
         file_name,
         detected_type,
+        installer_technology,
         detected_arch,
-        signature_sha256,
-    })
+        arch_source,
+        authenticode,
+        msi,
+        version_info,
+        archive,
+        icons,
+        dependencies,
+        commands,
+        burn,
+        ..HashResult::default()
+    };
+    if let Some(ref msi) = result.msi {
+        result.protocols = msi.protocols.clone();
+        result.file_extensions = msi.file_extensions.clone();
+    }
+    apply_inno(&mut result, inno);
+    apply_package(&mut result, signature_sha256, msix, package_icons);
+    result
+}
+
+/// Records an Inno Setup header. Installers that let `/ALLUSERS` and `/CURRENTUSER`
+/// pick the install mode get one installer per scope.
+fn apply_inno(result: &mut HashResult, inno: Option<InnoInfo>) {
+    let Some(info) = inno else {
+        return;
+    };
+    if info.privileges_override_allowed {
+        let architecture = result.detected_arch.clone().unwrap_or_else(|| "x86".to_string());
+        result.suggested_installers = [("user", "/CURRENTUSER"), ("machine", "/ALLUSERS")]
+            .into_iter()
+            .map(|(scope, switch)| InstallerEntry {
+                architecture: architecture.clone(),
+                installer_type: "inno".to_string(),
+                installer_sha256: result.sha256.clone(),
+                scope: Some(scope.to_string()),
+                installer_switches: Some(InstallerSwitches {
+                    custom: Some(switch.to_string()),
+                    ..Default::default()
+                }),
+                product_code: info.product_code.clone(),
+                installation_metadata: info
+                    .default_dir_name
+                    .as_deref()
+                    .and_then(|dir| inno::install_location(dir, Some(scope)))
+                    .map(|location| InstallationMetadata {
+                        default_install_location: Some(location),
+                    }),
+                installer_technology: Some("inno".to_string()),
+                ..Default::default()
+            })
+            .collect();
+    }
+    result.inno = Some(info);
+}
+
+/// Records what was read from an `.msix`/`.appx` package, suggesting one installer per
+/// architecture of a bundle.
+fn apply_package(
+    result: &mut HashResult,
+    signature_sha256: Option<String>,
+    msix: Option<MsixInfo>,
+    icons: Vec<IconInfo>,
+) {
+    if let Some(arch) = msix.as_ref().and_then(|m| m.processor_architecture.clone()) {
+        result.detected_arch = Some(arch);
+        result.arch_source = Some("appxManifest".to_string());
+    }
+
+    let bundle_archs = msix
+        .as_ref()
+        .filter(|m| m.is_bundle)
+        .map(|m| m.bundle_architectures())
+        .unwrap_or_default();
+    if let [arch] = bundle_archs.as_slice() {
+        result.detected_arch = Some(arch.clone());
+        result.arch_source = Some("appxManifest".to_string());
+    }
+
+    if let Some(bundle) = msix.as_ref().filter(|m| m.is_bundle) {
+        result.suggested_installers = bundle_archs
+            .into_iter()
+            .map(|architecture| InstallerEntry {
+                architecture,
+                installer_type: "msix".to_string(),
+                installer_sha256: result.sha256.clone(),
+                signature_sha256: signature_sha256.clone(),
+                package_family_name: bundle.package_family_name.clone(),
+                ..Default::default()
+            })
+            .collect();
+    }
+    if let Some(ref msix) = msix {
+        deps::merge(&mut result.dependencies, deps::from_msix(&msix.package_dependencies));
+        result.commands = msix.commands.clone();
+        result.protocols = msix.protocols.clone();
+        result.file_extensions = msix.file_extensions.clone();
+    }
+    result.signature_sha256 = signature_sha256;
+    result.msix = msix;
+    if !icons.is_empty() {
+        result.icons = icons;
+    }
+}
+
+/// Error of a cancelled job, returned as is so the UI can tell it from failures.
+const CANCELLED: &str = "HASH_JOB_CANCELLED";
+/// Minimum delay between two progress events of the same job.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(150);
+
+static JOBS: OnceLock<Mutex<HashMap<String, CancellationToken>>> = OnceLock::new();
+
+fn jobs() -> &'static Mutex<HashMap<String, CancellationToken>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashProgress {
+    pub job_id: String,
+    /// `downloading`, `hashing` or `analyzing`.
+    pub phase: String,
+    pub bytes_done: u64,
+    pub total_bytes: Option<u64>,
+    pub bytes_per_second: u64,
+}
+
+/// A running hash, registered so that `cancel_job` can reach it until it is dropped.
+pub struct HashJob {
+    id: String,
+    cancel: CancellationToken,
+    on_progress: Box<dyn Fn(HashProgress) + Send + Sync>,
+}
+
+impl HashJob {
+    /// Registers a job; fails while another job with the same id is running, since the
+    /// two could not be cancelled separately.
+    pub fn new(id: String, on_progress: impl Fn(HashProgress) + Send + Sync + 'static) -> Result<Self, String> {
+        let cancel = CancellationToken::new();
+        let mut jobs = jobs().lock().map_err(|_| "Hash job registry is unavailable".to_string())?;
+        if jobs.contains_key(&id) {
+            return Err(format!("A hash job with id {} is already running", id));
+        }
+        jobs.insert(id.clone(), cancel.clone());
+        Ok(HashJob {
+            id,
+            cancel,
+            on_progress: Box::new(on_progress),
+        })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Runs `future` unless the job is cancelled first.
+    async fn or_cancelled<T>(&self, future: impl Future<Output = T>) -> Result<T, Failure> {
+        tokio::select! {
+            _ = self.cancel.cancelled() => Err(Failure::Fatal(CANCELLED.to_string())),
+            value = future => Ok(value),
+        }
+    }
+}
+
+impl Drop for HashJob {
+    fn drop(&mut self) {
+        if let Ok(mut jobs) = jobs().lock() {
+            jobs.remove(&self.id);
+        }
+    }
+}
+
+/// Stops a running job; returns false when no job has that id.
+pub fn cancel_job(id: &str) -> bool {
+    let token = jobs().lock().ok().and_then(|jobs| jobs.get(id).cloned());
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Turns byte counts into throttled progress events for one phase of a job.
+struct ProgressMeter<'a> {
+    job: &'a HashJob,
+    phase: &'static str,
+    total: Option<u64>,
+    started: Instant,
+    /// Bytes already there when the meter started, e.g. when a download resumes.
+    baseline: u64,
+    last_emit: Option<Instant>,
+}
+
+impl<'a> ProgressMeter<'a> {
+    fn new(job: &'a HashJob, phase: &'static str, total: Option<u64>, baseline: u64) -> Self {
+        ProgressMeter {
+            job,
+            phase,
+            total,
+            started: Instant::now(),
+            baseline,
+            last_emit: None,
+        }
+    }
+
+    fn update(&mut self, bytes_done: u64, force: bool) {
+        let now = Instant::now();
+        if !force && self.last_emit.is_some_and(|t| now - t < PROGRESS_INTERVAL) {
+            return;
+        }
+        self.last_emit = Some(now);
+        let elapsed = (now - self.started).as_secs_f64();
+        let bytes_per_second = if elapsed > 0.0 {
+            (bytes_done.saturating_sub(self.baseline) as f64 / elapsed) as u64
+        } else {
+            0
+        };
+        (self.job.on_progress)(HashProgress {
+            job_id: self.job.id.clone(),
+            phase: self.phase.to_string(),
+            bytes_done,
+            total_bytes: self.total,
+            bytes_per_second,
+        });
+    }
+}
+
+/// Whether a failed attempt is worth repeating.
+enum Failure {
+    Retry(String),
+    Fatal(String),
+}
+
+/// Distinguishes the temp files of downloads that share a file name.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A download's temp file, deleted when dropped unless the cache has moved it away.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(file_name: &str) -> Self {
+        let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        TempPath(std::env::temp_dir().join(format!(
+            "unicreate_{}_{}_{}",
+            std::process::id(),
+            unique,
+            safe_temp_name(file_name)
+        )))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Where downloaded bytes go.
+enum Sink {
+    File { path: TempPath, file: std::fs::File },
+    /// Packages are read through their zip central directory, so only the last bytes
+    /// are kept; anything else they need is fetched with range requests.
+    Tail(VecDeque<u8>),
+}
+
+/// A download in progress, kept across attempts so a retry can resume it with a Range request.
+struct Transfer {
+    sink: Sink,
+    file_name: String,
+    hasher: Sha256,
+    size: u64,
+    head: Vec<u8>,
+    sniffed: bool,
+    /// Validators of the response the bytes came from.
+    validators: Validators,
+    redirect_chain: Vec<String>,
+    final_url: String,
+}
+
+impl Transfer {
+    fn restart(&mut self) -> Result<(), Failure> {
+        match &mut self.sink {
+            Sink::File { file, .. } => file
+                .set_len(0)
+                .and_then(|_| file.seek(SeekFrom::Start(0)).map(|_| ()))
+                .map_err(|e| Failure::Fatal(format!("Temp file error: {}", e)))?,
+            Sink::Tail(tail) => tail.clear(),
+        }
+        self.hasher = Sha256::new();
+        self.size = 0;
+        self.head.clear();
+        self.sniffed = false;
+        Ok(())
+    }
+
+    fn append(&mut self, chunk: &[u8]) -> Result<(), Failure> {
+        if !self.sniffed {
+            let take = chunk.len().min(SNIFF_LEN - self.head.len());
+            self.head.extend_from_slice(&chunk[..take]);
+            if self.head.len() == SNIFF_LEN {
+                self.sniffed = true;
+                reject_text_payload(&self.head).map_err(Failure::Fatal)?;
+            }
+        }
+        match &mut self.sink {
+            Sink::File { file, .. } => file
+                .write_all(chunk)
+                .map_err(|e| Failure::Fatal(format!("Write error: {}", e)))?,
+            Sink::Tail(tail) => {
+                let keep = chunk.len().min(PACKAGE_TAIL_LEN);
+                tail.extend(&chunk[chunk.len() - keep..]);
+                let excess = tail.len().saturating_sub(PACKAGE_TAIL_LEN);
+                tail.drain(..excess);
+            }
+        }
+        self.size += chunk.len() as u64;
+        self.hasher.update(chunk);
+        Ok(())
+    }
+}
+
+/// `ETag` and `Last-Modified` of a response, used to resume and to revalidate the cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_response(response: &reqwest::Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// The validator `If-Range` accepts: a strong ETag, or Last-Modified as a fallback.
+    fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+/// First byte position of a `Content-Range: bytes start-end/total` header.
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.trim().strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// One request, resuming `transfer` when it already holds bytes from a matching response.
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    visited: &Mutex<Vec<String>>,
+    transfer: &mut Option<Transfer>,
+    stream_packages: bool,
+    job: &HashJob,
+) -> Result<(), Failure> {
+    if let Ok(mut chain) = visited.lock() {
+        chain.clear();
+        chain.push(url.to_string());
+    }
+
+    let mut request = client.get(url).header("User-Agent", "UniCreate/1.0");
+    let resume_from = transfer.as_ref().and_then(|t| {
+        let validator = t.validators.if_range().filter(|_| t.size > 0)?;
+        Some((t.size, validator.to_string()))
+    });
+    if let Some((offset, ref validator)) = resume_from {
+        request = request
+            .header(RANGE, format!("bytes={}-", offset))
+            .header(IF_RANGE, validator.as_str());
+    }
+
+    let response = job
+        .or_cancelled(request.send())
+        .await?
+        .map_err(|e| Failure::Retry(format!("Download failed: {}", e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = format!("HTTP error: {}", status);
+        return Err(if is_retryable_status(status) {
+            Failure::Retry(message)
+        } else {
+            Failure::Fatal(message)
+        });
+    }
+
+    let validators = Validators::from_response(&response);
+    let redirect_chain = visited.lock().map(|chain| chain.clone()).unwrap_or_default();
+    let final_url = response.url().clone();
+
+    let t = match transfer {
+        Some(t) => {
+            // The server may ignore the range or serve a newer file; only append to the same bytes
+            let resumed = status == StatusCode::PARTIAL_CONTENT
+                && resume_from.is_some_and(|(offset, _)| content_range_start(&response) == Some(offset))
+                && validators == t.validators;
+            if !resumed {
+                t.restart()?;
+                t.validators = validators;
+            }
+            t.redirect_chain = redirect_chain;
+            t.final_url = final_url.to_string();
+            t
+        }
+        None => {
+            let file_name = response
+                .headers()
+                .get("content-disposition")
+                .and_then(|h| h.to_str().ok())
+                .and_then(content_disposition_file_name)
+                .or_else(|| file_name_from_url(&final_url))
+                .or_else(|| reqwest::Url::parse(url).ok().as_ref().and_then(file_name_from_url))
+                .map(|name| base_name(&name))
+                .unwrap_or_else(|| "unknown".to_string());
+
+            // Save to a temp file so the headers and containers can be inspected afterwards;
+            // packages can skip it unless range reads already failed for this download
+            let sink = if stream_packages && detect_installer_type(&file_name).as_deref() == Some("msix") {
+                Sink::Tail(VecDeque::with_capacity(PACKAGE_TAIL_LEN))
+            } else {
+                let path = TempPath::new(&file_name);
+                let file = std::fs::File::create(path.path())
+                    .map_err(|e| Failure::Fatal(format!("Temp file error: {}", e)))?;
+                Sink::File { path, file }
+            };
+            transfer.insert(Transfer {
+                sink,
+                file_name,
+                hasher: Sha256::new(),
+                size: 0,
+                head: Vec::with_capacity(SNIFF_LEN),
+                sniffed: false,
+                validators,
+                redirect_chain,
+                final_url: final_url.to_string(),
+            })
+        }
+    };
+
+    let total = response.content_length().map(|len| len + t.size);
+    let mut meter = ProgressMeter::new(job, "downloading", total, t.size);
+    meter.update(t.size, true);
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = job.or_cancelled(stream.next()).await? {
+        let chunk = chunk.map_err(|e| Failure::Retry(format!("Stream error: {}", e)))?;
+        t.append(&chunk)?;
+        meter.update(t.size, false);
+    }
+    meter.update(t.size, true);
+    Ok(())
+}
+
+/// Returns the cached analysis of `url` once the server confirms the file is unchanged.
+async fn cached_result(client: &reqwest::Client, url: &str, job: &HashJob) -> Option<HashResult> {
+    let key = url.to_string();
+    let entry = blocking(move || cache::lookup(&key)).await.ok()??;
+    let stored = Validators {
+        etag: entry.etag.clone(),
+        last_modified: entry.last_modified.clone(),
+    };
+    let mut request = client.get(url).header("User-Agent", "UniCreate/1.0");
+    if let Some(ref etag) = stored.etag {
+        request = request.header(IF_NONE_MATCH, etag.as_str());
+    }
+    if let Some(ref last_modified) = stored.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+    }
+    let response = job.or_cancelled(request.send()).await.ok()?.ok()?;
+    let unchanged = match response.status() {
+        StatusCode::NOT_MODIFIED => true,
+        // Servers that ignore conditional requests still tell us what they are serving
+        status if status.is_success() => {
+            !stored.is_empty()
+                && Validators::from_response(&response) == stored
+                && response.content_length() == Some(entry.content_length)
+        }
+        _ => false,
+    };
+    drop(response);
+    if !unchanged {
+        return None;
+    }
+
+    let mut result = if entry.analysis_version == cache::ANALYSIS_VERSION {
+        let url = url.to_string();
+        blocking(move || cache::touch(&url, None)).await.ok()?;
+        entry.result
+    } else {
+        // Older analysis: redo it from the kept installer, or download again without one
+        let path = cache::file_path(&entry)?;
+        let previous = entry.result;
+        let url = url.to_string();
+        blocking(move || {
+            let mut result = analyze_file(&path, previous.file_name, previous.sha256, previous.file_size);
+            for suggested in &mut result.suggested_installers {
+                suggested.installer_url = url.clone();
+            }
+            result.redirect_chain = previous.redirect_chain;
+            result.final_url = previous.final_url;
+            cache::touch(&url, Some(&result));
+            result
+        })
+        .await
+        .ok()?
+    };
+    result.from_cache = true;
+    Some(result)
+}
+
+/// Downloads and analyzes `url`; `token` is only used to read its GitHub release.
+pub async fn download_and_hash(
+    url: String,
+    token: Option<&str>,
+    job: &HashJob,
+) -> Result<HashResult, String> {
+    let mut result = download_and_analyze(url.clone(), job).await?;
+    // Checked on every run, cached or not: the published list may have been corrected since
+    result.checksum = job
+        .or_cancelled(checksums::verify(&url, &result.sha256, token))
+        .await
+        .map_err(|_| CANCELLED.to_string())?;
+    Ok(result)
+}
+
+async fn download_and_analyze(url: String, job: &HashJob) -> Result<HashResult, String> {
+    let visited = Arc::new(Mutex::new(vec![url.clone()]));
+    let recorder = Arc::clone(&visited);
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                return attempt.error("Too many redirects");
+            }
+            if let Ok(mut chain) = recorder.lock() {
+                chain.push(attempt.url().to_string());
+            }
+            attempt.follow()
+        }))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+
+    if let Some(result) = cached_result(&client, &url, job).await {
+        return Ok(result);
+    }
+
+    // Packages are first streamed without a temp copy; if the server then refuses the
+    // range reads their analysis needs, they are downloaded once more to disk
+    let mut stream_packages = true;
+    loop {
+        let transfer = download(&client, &url, &visited, stream_packages, job).await?;
+        let Transfer {
+            sink,
+            file_name,
+            hasher,
+            size: file_size,
+            validators,
+            redirect_chain,
+            final_url,
+            ..
+        } = transfer;
+
+        let hash = format!("{:X}", hasher.finalize());
+        ProgressMeter::new(job, "analyzing", Some(file_size), file_size).update(file_size, true);
+        let (mut result, kept) = match sink {
+            Sink::File { path, file } => {
+                drop(file);
+                let (result, path) = blocking(move || {
+                    let result = analyze_file(path.path(), file_name, hash, file_size);
+                    (result, path)
+                })
+                .await?;
+                (result, Some(path))
+            }
+            Sink::Tail(tail) => {
+                let package = PackageDownload {
+                    client: &client,
+                    url: &final_url,
+                    validators: &validators,
+                    size: file_size,
+                };
+                match analyze_streamed_package(&package, tail, &file_name, &hash, job).await? {
+                    Some(result) => (result, None),
+                    None => {
+                        stream_packages = false;
+                        continue;
+                    }
+                }
+            }
+        };
+        for entry in &mut result.suggested_installers {
+            entry.installer_url = url.clone();
+        }
+        result.redirect_chain = redirect_chain;
+        result.final_url = Some(final_url);
+
+        // Without validators a later hit could not be revalidated, so it is not worth keeping
+        if !validators.is_empty() {
+            let url = url.clone();
+            result = blocking(move || {
+                cache::store(&url, validators.etag, validators.last_modified, &result, kept.as_ref().map(TempPath::path));
+                result
+            })
+            .await?;
+        }
+        return Ok(result);
+    }
+}
+
+/// Downloads `url` with retries, checking the payload is not an error page.
+async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    visited: &Mutex<Vec<String>>,
+    stream_packages: bool,
+    job: &HashJob,
+) -> Result<Transfer, String> {
+    let mut transfer: Option<Transfer> = None;
+    let mut outcome = Err(String::new());
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            let delay = tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1));
+            if job.or_cancelled(delay).await.is_err() {
+                outcome = Err(CANCELLED.to_string());
+                break;
+            }
+        }
+        match download_attempt(client, url, visited, &mut transfer, stream_packages, job).await {
+            Ok(()) => {
+                outcome = Ok(());
+                break;
+            }
+            Err(Failure::Fatal(e)) => {
+                outcome = Err(e);
+                break;
+            }
+            Err(Failure::Retry(e)) => {
+                outcome = Err(format!("{} (after {} attempts)", e, attempt + 1));
+            }
+        }
+    }
+
+    let Some(transfer) = transfer else {
+        return Err(outcome.err().unwrap_or_else(|| "Download failed".to_string()));
+    };
+
+    // Payloads shorter than the sniffing window are checked once the stream ends
+    let payload_check = outcome.and_then(|_| {
+        if transfer.size == 0 {
+            Err("The server returned an empty file".to_string())
+        } else if !transfer.sniffed {
+            reject_text_payload(&transfer.head)
+        } else {
+            Ok(())
+        }
+    });
+    // Dropping a rejected transfer deletes its temp file
+    payload_check?;
+    Ok(transfer)
+}
+
+/// A finished package download whose missing parts can be fetched by range.
+struct PackageDownload<'a> {
+    client: &'a reqwest::Client,
+    url: &'a str,
+    validators: &'a Validators,
+    size: u64,
+}
+
+/// Fetches `len` bytes at `offset`, only accepting them from the same file version.
+async fn fetch_range(package: &PackageDownload<'_>, offset: u64, len: u64, job: &HashJob) -> Option<Vec<u8>> {
+    let end = (offset + len).min(package.size) - 1;
+    let response = job
+        .or_cancelled(
+            package
+                .client
+                .get(package.url)
+                .header("User-Agent", "UniCreate/1.0")
+                .header(RANGE, format!("bytes={}-{}", offset, end))
+                .send(),
+        )
+        .await
+        .ok()?
+        .ok()?;
+    let same_file = package.validators.is_empty() || Validators::from_response(&response) == *package.validators;
+    if response.status() != StatusCode::PARTIAL_CONTENT || content_range_start(&response) != Some(offset) || !same_file {
+        return None;
+    }
+    let data = job.or_cancelled(response.bytes()).await.ok()?.ok()?;
+    (data.len() as u64 == end - offset + 1).then(|| data.to_vec())
+}
+
+/// Analyzes a streamed package from its kept tail, fetching whatever else the zip reader
+/// asks for. `None` when those reads cannot be served.
+async fn analyze_streamed_package(
+    package: &PackageDownload<'_>,
+    tail: VecDeque<u8>,
+    file_name: &str,
+    sha256: &str,
+    job: &HashJob,
+) -> Result<Option<HashResult>, String> {
+    let mut file = SparseFile::new(package.size);
+    file.insert(package.size - tail.len() as u64, tail.into());
+    for _ in 0..MAX_RANGE_REQUESTS {
+        let (signature_sha256, msix, icons) = msix::analyze(&mut file);
+        let Some((offset, len)) = file.take_miss() else {
+            let (detected_arch, arch_source) = resolve_architecture(None, file_name);
+            let mut result = HashResult {
+                sha256: sha256.to_string(),
+                file_size: package.size,
+                file_name: file_name.to_string(),
+                detected_type: Some("msix".to_string()),
+                detected_arch,
+                arch_source,
+                ..HashResult::default()
+            };
+            apply_package(&mut result, signature_sha256, msix, icons);
+            return Ok(Some(result));
+        };
+        let data = fetch_range(package, offset, (len as u64).max(RANGE_CHUNK_LEN), job).await;
+        if job.is_cancelled() {
+            return Err(CANCELLED.to_string());
+        }
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        file.insert(offset, data);
+    }
+    Ok(None)
+}
+
+/// Runs parsing or cache I/O on the blocking pool, so it cannot stall the other
+/// downloads of a batch, which share one task.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Hash task failed: {}", e))
 }
 
-pub fn hash_local_file(path: &str) -> Result<HashResult, String> {
+/// Hashes a local file on the blocking pool so the async runtime stays responsive.
+pub async fn hash_local_file(path: String, job: HashJob) -> Result<HashResult, String> {
+    blocking(move || hash_local_file_blocking(&path, &job)).await?
+}
+
+fn hash_local_file_blocking(path: &str, job: &HashJob) -> Result<HashResult, String> {
     let file_path = Path::new(path);
     if !file_path.exists() {
         return Err("File not found".to_string());
@@ -150,34 +1205,209 @@ pub fn hash_local_file(path: &str) -> Result<HashResult, String> {
         .map(|f| f.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    let detected_type = detect_installer_type(&file_name);
-    let detected_arch = detect_architecture(&file_name);
-    let is_msix = matches!(detected_type.as_deref(), Some("msix"));
-
     let mut file = std::fs::File::open(file_path).map_err(|e| format!("Cannot open: {}", e))?;
+    let total = file.metadata().ok().map(|m| m.len());
+    let mut meter = ProgressMeter::new(job, "hashing", total, 0);
     let mut hasher = Sha256::new();
     let mut file_size: u64 = 0;
     let mut buf = [0u8; 65536];
     loop {
+        if job.is_cancelled() {
+            return Err(CANCELLED.to_string());
+        }
         let n = file.read(&mut buf).map_err(|e| format!("Read error: {}", e))?;
         if n == 0 { break; }
         hasher.update(&buf[..n]);
         file_size += n as u64;
+        meter.update(file_size, false);
     }
+    meter.update(file_size, true);
     let hash = format!("{:X}", hasher.finalize());
+    ProgressMeter::new(job, "analyzing", Some(file_size), file_size).update(file_size, true);
 
-    let signature_sha256 = if is_msix {
-        extract_msix_signature_hash(file_path)
-    } else {
-        None
-    };
+    Ok(analyze_file(file_path, file_name, hash, file_size))
+}
 
-    Ok(HashResult {
-        sha256: hash,
-        file_size,
-        file_name,
-        detected_type,
-        detected_arch,
-        signature_sha256,
-    })
+/// Hashes at most this many batch items at a time.
+const MAX_CONCURRENT_HASHES: usize = 3;
+
+/// One batch item: either a `url` to download or a local `path`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashRequest {
+    pub job_id: String,
+    pub url: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchHashResult {
+    pub job_id: String,
+    pub result: Option<HashResult>,
+    pub error: Option<String>,
+}
+
+/// Hashes every request with bounded concurrency, returning results in request order.
+/// `new_job` creates the progress/cancellation handle of each item; an item whose job
+/// cannot be registered fails on its own.
+pub async fn hash_batch(
+    requests: Vec<HashRequest>,
+    token: Option<&str>,
+    new_job: impl Fn(String) -> Result<HashJob, String>,
+) -> Vec<BatchHashResult> {
+    // Every job is registered up front so items still queued can be cancelled too
+    let jobs: Vec<(HashRequest, Result<HashJob, String>)> = requests
+        .into_iter()
+        .map(|request| {
+            let job = new_job(request.job_id.clone());
+            (request, job)
+        })
+        .collect();
+    let tasks = jobs.into_iter().map(|(request, job)| {
+        async move {
+            let outcome = match (job, request.url, request.path) {
+                (Err(e), _, _) => Err(e),
+                (Ok(job), _, _) if job.is_cancelled() => Err(CANCELLED.to_string()),
+                (Ok(job), Some(url), _) => download_and_hash(url, token, &job).await,
+                (Ok(job), None, Some(path)) => hash_local_file(path, job).await,
+                (Ok(_), None, None) => Err("Neither a URL nor a path was given".to_string()),
+            };
+            let (result, error) = match outcome {
+                Ok(result) => (Some(result), None),
+                Err(e) => (None, Some(e)),
+            };
+            BatchHashResult {
+                job_id: request.job_id,
+                result,
+                error,
+            }
+        }
+    });
+    futures_util::stream::iter(tasks)
+        .buffered(MAX_CONCURRENT_HASHES)
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_disposition_quoted_and_plain() {
+        assert_eq!(
+            content_disposition_file_name(r#"attachment; filename="My Setup; v2.exe""#).as_deref(),
+            Some("My Setup; v2.exe")
+        );
+        assert_eq!(
+            content_disposition_file_name(r#"attachment; filename="a \"b\".exe""#).as_deref(),
+            Some(r#"a "b".exe"#)
+        );
+        assert_eq!(content_disposition_file_name("attachment; filename=setup.exe").as_deref(), Some("setup.exe"));
+        assert_eq!(content_disposition_file_name("attachment"), None);
+        assert_eq!(content_disposition_file_name(r#"attachment; filename="  ""#), None);
+    }
+
+    #[test]
+    fn content_disposition_prefers_rfc5987() {
+        assert_eq!(
+            content_disposition_file_name("attachment; filename=fallback.exe; filename*=UTF-8''%C3%A9t%C3%A9%20setup.exe")
+                .as_deref(),
+            Some("été setup.exe")
+        );
+        assert_eq!(
+            content_disposition_file_name("attachment; FILENAME*=iso-8859-1'en'caf%E9.msi").as_deref(),
+            Some("café.msi")
+        );
+    }
+
+    #[test]
+    fn content_disposition_paths_are_reduced_to_a_safe_name() {
+        let name = content_disposition_file_name(r#"attachment; filename="../../evil.exe""#).unwrap();
+        assert_eq!(base_name(&name), "evil.exe");
+        let name = content_disposition_file_name("attachment; filename*=UTF-8''..%5C..%5Cevil.exe").unwrap();
+        assert_eq!(base_name(&name), "evil.exe");
+        assert_eq!(safe_temp_name(".."), "download");
+        assert_eq!(safe_temp_name("a:b?.exe"), "a_b_.exe");
+    }
+
+    #[test]
+    fn inno_override_suggests_one_installer_per_scope() {
+        let mut result = HashResult {
+            sha256: "ABC".to_string(),
+            detected_arch: Some("x64".to_string()),
+            ..Default::default()
+        };
+        let info = InnoInfo {
+            product_code: Some("Contoso_is1".to_string()),
+            default_dir_name: Some("{autopf}\\Contoso".to_string()),
+            privileges_required: Some("admin".to_string()),
+            privileges_override_allowed: true,
+            ..Default::default()
+        };
+        apply_inno(&mut result, Some(info));
+
+        let entries: Vec<_> = result
+            .suggested_installers
+            .iter()
+            .map(|i| {
+                (
+                    i.scope.as_deref(),
+                    i.installer_switches.as_ref().and_then(|s| s.custom.as_deref()),
+                    i.installation_metadata.as_ref().and_then(|m| m.default_install_location.as_deref()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (Some("user"), Some("/CURRENTUSER"), Some("%LocalAppData%\\Programs\\Contoso")),
+                (Some("machine"), Some("/ALLUSERS"), Some("%ProgramFiles%\\Contoso")),
+            ]
+        );
+        for installer in &result.suggested_installers {
+            assert_eq!(installer.architecture, "x64");
+            assert_eq!(installer.installer_type, "inno");
+            assert_eq!(installer.installer_sha256, "ABC");
+            assert_eq!(installer.product_code.as_deref(), Some("Contoso_is1"));
+        }
+        assert!(result.inno.is_some());
+    }
+
+    #[test]
+    fn fixed_inno_scope_suggests_no_installers() {
+        let mut result = HashResult::default();
+        let info = InnoInfo {
+            privileges_required: Some("lowest".to_string()),
+            scope: Some("user".to_string()),
+            ..Default::default()
+        };
+        apply_inno(&mut result, Some(info));
+        assert!(result.suggested_installers.is_empty());
+        assert_eq!(result.inno.and_then(|i| i.scope).as_deref(), Some("user"));
+    }
+
+    #[test]
+    fn cancelled_jobs_fail_with_the_cancellation_code() {
+        let path = std::env::current_exe().unwrap().to_string_lossy().to_string();
+        let job = HashJob::new("test-cancelled-local".to_string(), |_| {}).unwrap();
+        assert!(cancel_job("test-cancelled-local"));
+        assert_eq!(hash_local_file_blocking(&path, &job).unwrap_err(), CANCELLED);
+        drop(job);
+        assert!(!cancel_job("test-cancelled-local"));
+
+        let requests = ["test-cancelled-queued", "test-cancelled-path"].map(|id| HashRequest {
+            job_id: id.to_string(),
+            url: None,
+            path: Some(path.clone()),
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let results = runtime.block_on(hash_batch(requests.into(), None, |id| {
+            let job = HashJob::new(id.clone(), |_| {})?;
+            cancel_job(&id);
+            Ok(job)
+        }));
+        assert!(results.iter().all(|r| r.result.is_none() && r.error.as_deref() == Some(CANCELLED)));
+    }
 }
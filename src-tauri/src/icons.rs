@@ -0,0 +1,325 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek};
+
+use crate::pe::{self, le_u16, PeHeader, ResourceName};
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Resolutions winget accepts for `IconResolution`; anything else is `custom`.
+const RESOLUTIONS: &[u32] = &[16, 20, 24, 30, 32, 36, 40, 48, 60, 64, 72, 80, 96, 256];
+const MAX_ICONS: usize = 16;
+/// Larger images are not worth hosting as a manifest icon.
+const MAX_ICON_BYTES: usize = 512 * 1024;
+/// `VisualElements` attributes naming the package logos, besides `Properties/Logo`.
+const LOGO_ATTRIBUTES: &[&str] = &["Square44x44Logo", "Square150x150Logo"];
+
+/// An icon found in an installer, ready to be hosted and listed under `Icons`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IconInfo {
+    /// `ico` or `png`, as `IconFileType` expects.
+    pub file_type: String,
+    /// `IconResolution` value, e.g. `32x32` or `custom`.
+    pub resolution: String,
+    /// `light`, `dark` or `highContrast` for themed package assets.
+    pub theme: Option<String>,
+    pub file_size: u64,
+    pub sha256: String,
+    /// Resource id or package path the icon was read from.
+    pub source: String,
+    /// The icon file itself, base64 encoded, so it can be previewed and saved.
+    pub data: String,
+}
+
+fn resolution(width: u32, height: u32) -> String {
+    if width == height && RESOLUTIONS.contains(&width) {
+        format!("{}x{}", width, height)
+    } else {
+        "custom".to_string()
+    }
+}
+
+fn png_size(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(PNG_SIGNATURE) || data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+fn icon(file_type: &str, resolution: String, theme: Option<String>, source: String, data: Vec<u8>) -> IconInfo {
+    IconInfo {
+        file_type: file_type.to_string(),
+        resolution,
+        theme,
+        file_size: data.len() as u64,
+        sha256: format!("{:X}", Sha256::digest(&data)),
+        source,
+        data: base64::engine::general_purpose::STANDARD.encode(&data),
+    }
+}
+
+/// Wraps one `RT_ICON` image in a single-image `.ico` file.
+fn ico_file(entry: &[u8], image: &[u8]) -> Vec<u8> {
+    let mut file = Vec::with_capacity(22 + image.len());
+    file.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    // The group entry matches ICONDIRENTRY up to the size; only the id becomes an offset
+    file.extend_from_slice(&entry[..8]);
+    file.extend_from_slice(&(image.len() as u32).to_le_bytes());
+    file.extend_from_slice(&22u32.to_le_bytes());
+    file.extend_from_slice(image);
+    file
+}
+
+/// Extracts the images of the first `RT_GROUP_ICON`, the one Explorer shows.
+/// PNG-compressed images are returned as PNG, the others as single-image `.ico` files.
+pub fn from_pe<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Vec<IconInfo> {
+    let groups = pe::find_resources(reader, header, pe::RT_GROUP_ICON);
+    let Some(group) = groups.first().and_then(|g| pe::read_resource(reader, g)) else {
+        return Vec::new();
+    };
+    let images = pe::find_resources(reader, header, pe::RT_ICON);
+    let count = le_u16(&group, 4).unwrap_or(0) as usize;
+
+    let mut icons: Vec<IconInfo> = Vec::new();
+    for entry in group.get(6..).unwrap_or(&[]).chunks_exact(14).take(count) {
+        let Some(id) = le_u16(entry, 12) else {
+            continue;
+        };
+        let Some(image) = images
+            .iter()
+            .find(|r| r.name == ResourceName::Id(id as u32) && (r.size as usize) <= MAX_ICON_BYTES)
+            .and_then(|r| pe::read_resource(reader, r))
+        else {
+            continue;
+        };
+        // A stored size of 0 means 256 pixels
+        let dimension = |b: u8| if b == 0 { 256 } else { b as u32 };
+        let source = format!("RT_ICON {}", id);
+        let found = match png_size(&image) {
+            Some((width, height)) => icon("png", resolution(width, height), None, source, image),
+            None => {
+                let size = resolution(dimension(entry[0]), dimension(entry[1]));
+                icon("ico", size, None, source, ico_file(entry, &image))
+            }
+        };
+        // Groups often repeat a size at several color depths; keep the first of each
+        if !icons.iter().any(|i| i.resolution == found.resolution && i.resolution != "custom") {
+            icons.push(found);
+        }
+        if icons.len() == MAX_ICONS {
+            break;
+        }
+    }
+    // Biggest first: that is the one worth hosting when only one is
+    icons.sort_by_key(|i| std::cmp::Reverse(i.file_size));
+    icons
+}
+
+/// Theme of a package asset from its resource qualifiers, e.g. `Logo.theme-dark.png`.
+fn asset_theme(qualifiers: &str) -> Option<String> {
+    if qualifiers.contains("theme-dark") {
+        Some("dark".to_string())
+    } else if qualifiers.contains("theme-light") {
+        Some("light".to_string())
+    } else if qualifiers.contains("contrast-") {
+        Some("highContrast".to_string())
+    } else {
+        None
+    }
+}
+
+fn logo_paths(manifest_xml: &str) -> Vec<String> {
+    let Ok(doc) = roxmltree::Document::parse(manifest_xml) else {
+        return Vec::new();
+    };
+    let store_logo = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "Logo" && n.parent().is_some_and(|p| p.tag_name().name() == "Properties"))
+        .filter_map(|n| n.text());
+    let visual_logos = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "VisualElements")
+        .flat_map(|n| LOGO_ATTRIBUTES.iter().filter_map(move |a| n.attribute(*a)));
+    let mut paths: Vec<String> = store_logo
+        .chain(visual_logos)
+        .map(|p| p.trim().replace('\\', "/"))
+        .collect();
+    paths.dedup();
+    paths
+}
+
+/// Reads the PNG assets behind the logos declared by an `AppxManifest.xml`, including
+/// their scale, target size and theme variants.
+pub fn from_package<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, manifest_xml: &str) -> Vec<IconInfo> {
+    let mut icons: Vec<IconInfo> = Vec::new();
+    for logo in logo_paths(manifest_xml) {
+        let Some((stem, extension)) = logo.rsplit_once('.') else {
+            continue;
+        };
+        let stem = stem.to_ascii_lowercase();
+        let suffix = format!(".{}", extension.to_ascii_lowercase());
+        let variants: Vec<String> = archive
+            .file_names()
+            .filter(|name| {
+                let lower = name.to_ascii_lowercase();
+                lower.ends_with(&suffix)
+                    && (lower == format!("{}{}", stem, suffix) || lower.starts_with(&format!("{}.", stem)))
+            })
+            .map(|name| name.to_string())
+            .collect();
+
+        for name in variants {
+            let Ok(mut entry) = archive.by_name(&name) else {
+                continue;
+            };
+            if entry.size() as usize > MAX_ICON_BYTES {
+                continue;
+            }
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            if entry.read_to_end(&mut data).is_err() {
+                continue;
+            }
+            let Some((width, height)) = png_size(&data) else {
+                continue;
+            };
+            let qualifiers = name[stem.len()..name.len() - suffix.len()].to_ascii_lowercase();
+            let found = icon("png", resolution(width, height), asset_theme(&qualifiers), name, data);
+            let duplicate = icons.iter().any(|i| {
+                i.sha256 == found.sha256
+                    || (i.resolution != "custom" && i.resolution == found.resolution && i.theme == found.theme)
+            });
+            if !duplicate {
+                icons.push(found);
+            }
+            if icons.len() == MAX_ICONS {
+                return icons;
+            }
+        }
+    }
+    icons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pe::tests::image;
+    use std::io::{Cursor, Write};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend(13u32.to_be_bytes());
+        data.extend(b"IHDR");
+        data.extend(width.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend([8, 6, 0, 0, 0]);
+        data
+    }
+
+    /// A `GRPICONDIR` listing `(width, height, id)` entries.
+    fn group(entries: &[(u8, u8, u16)]) -> Vec<u8> {
+        let mut data = vec![0, 0, 1, 0];
+        data.extend((entries.len() as u16).to_le_bytes());
+        for &(width, height, id) in entries {
+            data.extend([width, height, 0, 0]);
+            data.extend(1u16.to_le_bytes());
+            data.extend(32u16.to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+            data.extend(id.to_le_bytes());
+        }
+        data
+    }
+
+    fn pe_icons(resources: &[(u32, u32, &[u8])]) -> Vec<IconInfo> {
+        let data = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, resources);
+        let mut reader = Cursor::new(&data);
+        let header = pe::read_header(&mut reader).unwrap();
+        from_pe(&mut reader, &header)
+    }
+
+    #[test]
+    fn extracts_the_first_icon_group() {
+        let bitmap = [0x28u8; 40];
+        let large = png(256, 256);
+        let icons = pe_icons(&[
+            (pe::RT_GROUP_ICON, 1, &group(&[(32, 32, 1), (32, 32, 2), (0, 0, 3), (48, 48, 9)])),
+            (pe::RT_GROUP_ICON, 2, &group(&[(16, 16, 4)])),
+            (pe::RT_ICON, 1, &bitmap),
+            (pe::RT_ICON, 2, &[0x29; 60]),
+            (pe::RT_ICON, 3, &large),
+            (pe::RT_ICON, 4, &bitmap),
+        ]);
+        let found: Vec<(&str, &str, &str)> = icons
+            .iter()
+            .map(|i| (i.file_type.as_str(), i.resolution.as_str(), i.source.as_str()))
+            .collect();
+        assert_eq!(found, [("ico", "32x32", "RT_ICON 1"), ("png", "256x256", "RT_ICON 3")]);
+
+        let ico = base64::engine::general_purpose::STANDARD.decode(&icons[0].data).unwrap();
+        assert_eq!(ico[..6], [0, 0, 1, 0, 1, 0]);
+        assert_eq!(ico[6..8], [32, 32]);
+        assert_eq!(ico[14..18], 40u32.to_le_bytes());
+        assert_eq!(ico[18..22], 22u32.to_le_bytes());
+        assert_eq!(ico[22..], bitmap);
+        assert_eq!(icons[0].file_size, 62);
+        assert_eq!(icons[1].sha256, format!("{:X}", Sha256::digest(&large)));
+    }
+
+    #[test]
+    fn damaged_icon_groups_give_no_icons() {
+        assert!(pe_icons(&[]).is_empty());
+        // Counts beyond the entries present, and a group cut short
+        let mut group = group(&[(32, 32, 1)]);
+        group[4] = 200;
+        assert_eq!(pe_icons(&[(pe::RT_GROUP_ICON, 1, &group), (pe::RT_ICON, 1, &[1; 8])]).len(), 1);
+        assert!(pe_icons(&[(pe::RT_GROUP_ICON, 1, &group[..12]), (pe::RT_ICON, 1, &[1; 8])]).is_empty());
+        assert!(pe_icons(&[(pe::RT_GROUP_ICON, 1, &[0, 0])]).is_empty());
+        assert_eq!(png_size(&png(64, 64)[..20]), None);
+    }
+
+    #[test]
+    fn reads_the_logo_variants_of_a_package() {
+        let manifest = r#"<Package xmlns="http://schemas.microsoft.com/appx/manifest/foundation/windows10"
+            xmlns:uap="http://schemas.microsoft.com/appx/manifest/uap/windows10">
+          <Properties><Logo>Assets\StoreLogo.png</Logo></Properties>
+          <Applications><Application>
+            <uap:VisualElements Square44x44Logo="Assets\Square44x44Logo.png" Square150x150Logo="Assets\Missing.png" />
+          </Application></Applications>
+        </Package>"#;
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let files = [
+            ("Assets/StoreLogo.scale-100.png", png(50, 50)),
+            ("Assets/Square44x44Logo.targetsize-48.png", png(48, 48)),
+            ("Assets/Square44x44Logo.targetsize-48_altform-unplated.png", png(48, 48)),
+            ("Assets/Square44x44Logo.targetsize-48_theme-dark.png", {
+                let mut dark = png(48, 48);
+                dark.push(1);
+                dark
+            }),
+            ("Assets/Square44x44LogoExtra.png", png(16, 16)),
+            ("Assets/Square44x44Logo.scale-200.png", b"not a png".to_vec()),
+        ];
+        for (name, data) in &files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        let mut archive = zip::ZipArchive::new(zip.finish().unwrap()).unwrap();
+
+        let icons = from_package(&mut archive, manifest);
+        let found: Vec<(&str, &str, Option<&str>)> = icons
+            .iter()
+            .map(|i| (i.source.as_str(), i.resolution.as_str(), i.theme.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Assets/StoreLogo.scale-100.png", "custom", None),
+                ("Assets/Square44x44Logo.targetsize-48.png", "48x48", None),
+                ("Assets/Square44x44Logo.targetsize-48_theme-dark.png", "48x48", Some("dark")),
+            ]
+        );
+        assert!(from_package(&mut archive, "<Package").is_empty());
+    }
+}
@@ -0,0 +1,683 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::pe::{self, le_u32, read_at, PeHeader, ResourceName};
+
+const RT_RCDATA: u32 = 10;
+/// Resource holding the setup loader offset table since Inno Setup 5.1.5.
+const OFFSET_TABLE_RESOURCE: u32 = 11111;
+const OFFSET_TABLE_ID: &[u8] = b"rDlPtS\xcd\xe6\xd7\x7b\x0b\x2a";
+/// Offset table of Inno Setup 4.1.6 to 5.1.4.
+const LEGACY_OFFSET_TABLE_ID: &[u8] = b"rDlPtS07\x87\x65\x56\x78";
+const SETUP_DATA_ID_LEN: usize = 64;
+/// Inno Setup 6.5 stores its encryption settings, preceded by their CRC32, between the
+/// setup data id and the setup header.
+const ENCRYPTION_HEADER_LEN: u64 = 4 + 49;
+/// The compressed header is stored in chunks, each preceded by its CRC32.
+const CHUNK_LEN: usize = 4096;
+/// Setup headers, entries included, are far smaller than this.
+const MAX_HEADER_BLOCK: u32 = 32 * 1024 * 1024;
+/// More entries than this means the fixed part of the header was misread.
+const MAX_ENTRY_COUNT: u32 = 1_000_000;
+const PRIVILEGES: &[&str] = &["none", "poweruser", "admin", "lowest"];
+const OVERRIDE_COMMAND_LINE: u8 = 0x01;
+const OVERRIDE_DIALOG: u8 = 0x02;
+
+/// Inno Setup constants that have a winget `DefaultInstallLocation` equivalent.
+const DIRECTORY_CONSTANTS: &[(&str, &str)] = &[
+    ("{commonpf}", "%ProgramFiles%"),
+    ("{pf}", "%ProgramFiles%"),
+    ("{commonpf32}", "%ProgramFiles(x86)%"),
+    ("{pf32}", "%ProgramFiles(x86)%"),
+    ("{commonpf64}", "%ProgramFiles%"),
+    ("{pf64}", "%ProgramFiles%"),
+    ("{userpf}", "%LocalAppData%\\Programs"),
+    ("{localappdata}", "%LocalAppData%"),
+    ("{userappdata}", "%AppData%"),
+    ("{commonappdata}", "%ProgramData%"),
+    ("{sd}", "%SystemDrive%"),
+    ("{win}", "%WinDir%"),
+];
+
+/// What an Inno Setup installer's setup header declares.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InnoInfo {
+    /// Version of Inno Setup that built the installer, e.g. `6.2.2`.
+    pub setup_version: String,
+    pub app_id: Option<String>,
+    /// `{AppId}_is1`, the uninstall key Inno Setup registers.
+    pub product_code: Option<String>,
+    pub app_name: Option<String>,
+    pub app_version: Option<String>,
+    pub app_publisher: Option<String>,
+    pub default_dir_name: Option<String>,
+    /// `DefaultDirName` with its directory constant resolved for winget.
+    pub default_install_location: Option<String>,
+    /// `none`, `poweruser`, `admin` or `lowest`.
+    pub privileges_required: Option<String>,
+    /// Whether `/ALLUSERS` and `/CURRENTUSER` may change the install mode.
+    pub privileges_override_allowed: bool,
+    /// `machine` or `user` when the install mode is fixed, none for dual-mode installers.
+    pub scope: Option<String>,
+}
+
+/// Reads strings and fixed fields from the decompressed setup header.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    unicode: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        le_u32(self.bytes(4)?, 0)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    fn binary(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    /// A string in the installer's encoding: UTF-16LE for Unicode builds.
+    fn string(&mut self) -> Option<String> {
+        let bytes = self.binary()?;
+        if self.unicode {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units))
+        } else {
+            Some(bytes.iter().map(|&b| b as char).collect())
+        }
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.bytes(len).map(|_| ())
+    }
+}
+
+/// File offset of the setup header, from the setup loader offset table.
+fn header_offset<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<u64> {
+    let table = pe::find_resources(reader, header, RT_RCDATA)
+        .into_iter()
+        .find(|r| r.name == ResourceName::Id(OFFSET_TABLE_RESOURCE))
+        .and_then(|r| pe::read_resource(reader, &r))?;
+    let id = table.get(..OFFSET_TABLE_ID.len())?;
+    if id == OFFSET_TABLE_ID {
+        // Revision 2 (Inno Setup 6.5) widened the offsets to 64 bits
+        match le_u32(&table, 12)? {
+            1 => le_u32(&table, 32).map(u64::from),
+            2 => Some(u64::from_le_bytes(table.get(40..48)?.try_into().ok()?)),
+            _ => None,
+        }
+    } else if id == LEGACY_OFFSET_TABLE_ID {
+        le_u32(&table, 28).map(u64::from)
+    } else {
+        None
+    }
+}
+
+fn parse_version(setup_data_id: &str) -> Option<(u32, u32, u32)> {
+    let start = setup_data_id.find('(')? + 1;
+    let end = start + setup_data_id[start..].find(')')?;
+    let mut parts = setup_data_id[start..end].split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??, parts.next().flatten().unwrap_or(0)))
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// Reads the compressed block at `offset`: the setup header when it follows the setup data id.
+fn read_header_block<R: Read + Seek>(reader: &mut R, offset: u64) -> Option<Vec<u8>> {
+    let prefix = read_at(reader, offset, 9)?;
+    // The block header carries the CRC32 of its stored size and compression flag
+    if le_u32(&prefix, 0)? != crc32(&prefix[4..]) {
+        return None;
+    }
+    let stored_size = le_u32(&prefix, 4)?;
+    if stored_size > MAX_HEADER_BLOCK {
+        return None;
+    }
+    let stored = read_at(reader, offset + 9, stored_size as usize)?;
+    let data: Vec<u8> = stored
+        .chunks(4 + CHUNK_LEN)
+        .flat_map(|chunk| chunk.get(4..).unwrap_or(&[]))
+        .copied()
+        .collect();
+    if prefix[8] == 0 {
+        return Some(data);
+    }
+
+    // LZMA1 properties followed by a stream that may lack an end marker
+    let mut output = Vec::new();
+    let options = lzma_rs::decompress::Options {
+        unpacked_size: lzma_rs::decompress::UnpackedSize::UseProvided(None),
+        memlimit: Some(MAX_HEADER_BLOCK as usize),
+        allow_incomplete: true,
+    };
+    lzma_rs::lzma_decompress_with_options(&mut data.as_slice(), &mut output, &options).ok()?;
+    Some(output)
+}
+
+/// Resolves the leading directory constant of `DefaultDirName`. `{autopf}` depends on
+/// the install mode, so it is only resolved once the scope is known.
+pub fn install_location(dir: &str, scope: Option<&str>) -> Option<String> {
+    let lower = dir.to_ascii_lowercase();
+    let (constant, value) = match scope {
+        Some("machine") if lower.starts_with("{autopf}") => ("{autopf}", "%ProgramFiles%"),
+        Some("user") if lower.starts_with("{autopf}") => ("{autopf}", "%LocalAppData%\\Programs"),
+        _ => DIRECTORY_CONSTANTS
+            .iter()
+            .find(|(constant, _)| lower.starts_with(constant))
+            .copied()?,
+    };
+    let rest = &dir[constant.len()..];
+    // Anything still in braces needs Setup's runtime to resolve
+    (!rest.contains('{')).then(|| format!("{}{}", value, rest))
+}
+
+/// Reads the fixed fields up to the privileges settings. Only the layouts of
+/// Inno Setup 5.5 and later are known; older ones yield `None`, and so does any field
+/// out of its range, which means the layout was misread.
+///
+/// `password_in_header` is false when Inno Setup 6.5 stored the password check in its
+/// separate encryption header.
+fn read_privileges(r: &mut Reader, version: (u32, u32, u32), password_in_header: bool) -> Option<(u8, u8)> {
+    if version < (5, 5, 0) {
+        return None;
+    }
+    // Language, custom message, permission, type, component, task, directory, ISSig key
+    // (6.5), file, file location, icon, ini, registry, delete, uninstall delete, run and
+    // uninstall run
+    let counts = if version >= (6, 5, 0) { 17 } else { 16 };
+    for _ in 0..counts {
+        if r.u32()? > MAX_ENTRY_COUNT {
+            return None;
+        }
+    }
+    // MinVersion and OnlyBelowVersion, then BackColor and BackColor2
+    r.skip(20 + 8)?;
+    if version < (5, 5, 7) {
+        r.skip(4)?; // WizardImageBackColor
+    }
+    if version >= (6, 0, 0) {
+        // WizardStyle, then WizardSizePercentX and WizardSizePercentY, which are at least 100
+        r.skip(1)?;
+        for _ in 0..2 {
+            if !(100..=1000).contains(&r.u32()?) {
+                return None;
+            }
+        }
+    }
+    if version >= (5, 5, 7) && r.u8()? > 2 {
+        return None; // WizardImageAlphaFormat
+    }
+    if password_in_header {
+        // SHA-1 and salt, or the KDF parameters since 6.4
+        r.skip(if version >= (6, 4, 0) { 48 } else { 28 })?;
+    }
+    let extra_disk_space_required = r.i64()?;
+    let slices_per_disk = r.u32()?;
+    let uninstall_log_mode = r.u8()?;
+    let dir_exists_warning = r.u8()?;
+    let privileges = r.u8()?;
+    let overrides = if version >= (5, 7, 0) { r.u8()? } else { 0 };
+    let valid = extra_disk_space_required >= 0
+        && (1..=26).contains(&slices_per_disk)
+        && uninstall_log_mode <= 2
+        && dir_exists_warning <= 2
+        && (privileges as usize) < PRIVILEGES.len()
+        && overrides <= (OVERRIDE_COMMAND_LINE | OVERRIDE_DIALOG);
+    valid.then_some((privileges, overrides))
+}
+
+/// Decodes the setup loader offset table and the setup header of an Inno Setup installer.
+pub fn analyze<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<InnoInfo> {
+    let offset = header_offset(reader, header)?;
+    read_setup_data(reader, offset)
+}
+
+/// Reads the setup data id and the setup header that follows it at `offset`.
+fn read_setup_data<R: Read + Seek>(reader: &mut R, offset: u64) -> Option<InnoInfo> {
+    let id = read_at(reader, offset, SETUP_DATA_ID_LEN)?;
+    let id = String::from_utf8_lossy(&id).trim_end_matches('\0').to_string();
+    if !id.contains("Inno Setup Setup Data") {
+        return None;
+    }
+    let version = parse_version(&id)?;
+    if version < (4, 1, 6) {
+        return None;
+    }
+    let start = offset + SETUP_DATA_ID_LEN as u64;
+    // Only 6.5 and later may put an encryption header first; its block CRC tells which
+    let (data, password_in_header) = match read_header_block(reader, start) {
+        Some(data) => (data, true),
+        None if version >= (6, 5, 0) => (read_header_block(reader, start + ENCRYPTION_HEADER_LEN)?, false),
+        None => return None,
+    };
+    let mut r = Reader {
+        data: &data,
+        pos: 0,
+        unicode: id.contains("(u)") || version >= (6, 0, 0),
+    };
+
+    let non_empty = |s: String| Some(s).filter(|s| !s.is_empty());
+    let app_name = r.string().and_then(non_empty);
+    r.string()?; // AppVerName
+    let app_id = r.string().and_then(non_empty);
+    r.string()?; // AppCopyright
+    let app_publisher = r.string().and_then(non_empty);
+    r.string()?; // AppPublisherURL
+    if version >= (5, 1, 13) {
+        r.string()?; // AppSupportPhone
+    }
+    r.string()?; // AppSupportURL
+    r.string()?; // AppUpdatesURL
+    let app_version = r.string().and_then(non_empty);
+    let default_dir_name = r.string().and_then(non_empty);
+
+    // DefaultGroupName, OutputBaseFilename, then the license and info files before 5.2.5
+    r.string()?;
+    r.string()?;
+    if version < (5, 2, 5) {
+        for _ in 0..3 {
+            r.binary()?;
+        }
+    }
+    // UninstallFilesDir, UninstallDisplayName, UninstallDisplayIcon, AppMutex and the
+    // default user name, organization and serial
+    for _ in 0..7 {
+        r.string()?;
+    }
+    if version < (5, 2, 5) {
+        r.binary()?; // Compiled [Code]
+    }
+    // AppReadmeFile, AppContact, AppComments and AppModifyPath
+    for _ in 0..4 {
+        r.string()?;
+    }
+    // CreateUninstallRegKey, Uninstallable, CloseApplicationsFilter, SetupMutex,
+    // ChangesEnvironment, ChangesAssociations, the two architecture directives,
+    // CloseApplicationsFilterExcludes and SevenZipLibraryName
+    let later_strings = [
+        (5, 3, 8),
+        (5, 3, 10),
+        (5, 5, 0),
+        (5, 5, 6),
+        (5, 6, 1),
+        (5, 6, 1),
+        (6, 3, 0),
+        (6, 3, 0),
+        (6, 4, 0),
+        (6, 5, 0),
+    ];
+    for since in later_strings {
+        if version >= since {
+            r.string()?;
+        }
+    }
+    if version >= (5, 2, 5) {
+        for _ in 0..3 {
+            r.binary()?; // License and info files
+        }
+    }
+    if ((5, 2, 1)..(5, 3, 10)).contains(&version) {
+        r.binary()?; // Uninstaller signature
+    }
+    if version >= (5, 2, 5) {
+        r.binary()?; // Compiled [Code]
+    }
+    if !r.unicode {
+        r.skip(32)?; // Lead bytes
+    }
+    let privileges = read_privileges(&mut r, version, password_in_header);
+
+    let privileges_required = privileges.map(|(p, _)| PRIVILEGES[p as usize].to_string());
+    let privileges_override_allowed = privileges.is_some_and(|(_, o)| o & OVERRIDE_COMMAND_LINE != 0);
+    let scope = match (privileges_required.as_deref(), privileges_override_allowed) {
+        (Some("admin" | "poweruser"), false) => Some("machine".to_string()),
+        (Some("lowest"), false) => Some("user".to_string()),
+        _ => None,
+    };
+
+    // `{{` escapes a brace; any other constant cannot be resolved here
+    let app_id = app_id.or_else(|| app_name.clone()).map(|id| id.replace("{{", "{"));
+    let product_code = app_id
+        .as_ref()
+        .filter(|id| !id.contains("{code:") && !id.contains("{#"))
+        .map(|id| format!("{}_is1", id));
+    let default_install_location = default_dir_name
+        .as_deref()
+        .and_then(|dir| install_location(dir, scope.as_deref()));
+
+    Some(InnoInfo {
+        setup_version: format!("{}.{}.{}", version.0, version.1, version.2),
+        app_id,
+        product_code,
+        app_name,
+        app_version,
+        app_publisher,
+        default_dir_name,
+        default_install_location,
+        privileges_required,
+        privileges_override_allowed,
+        scope,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    struct Setup {
+        version: (u32, u32, u32),
+        unicode: bool,
+        /// Inno Setup 6.5's separate encryption header in front of the setup header.
+        encryption_header: bool,
+        app_id: &'static str,
+        default_dir_name: &'static str,
+        privileges: u8,
+        overrides: u8,
+        slices_per_disk: u32,
+    }
+
+    impl Setup {
+        fn new(version: (u32, u32, u32)) -> Self {
+            Setup {
+                version,
+                unicode: true,
+                encryption_header: false,
+                app_id: "{{8F2C2A41-6E5B-4C1D-9D77-0C2E7B1A9F10}",
+                default_dir_name: "{autopf}\\Contoso App",
+                privileges: 2,
+                overrides: 0,
+                slices_per_disk: 1,
+            }
+        }
+
+        /// The setup header, field by field in the order Inno Setup writes it.
+        fn header(&self) -> Vec<u8> {
+            let v = self.version;
+            let mut out = Vec::new();
+            let string = |out: &mut Vec<u8>, value: &str| {
+                let bytes: Vec<u8> = if self.unicode {
+                    value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+                } else {
+                    value.bytes().collect()
+                };
+                out.extend((bytes.len() as u32).to_le_bytes());
+                out.extend(bytes);
+            };
+            let binary = |out: &mut Vec<u8>, value: &[u8]| {
+                out.extend((value.len() as u32).to_le_bytes());
+                out.extend(value);
+            };
+
+            string(&mut out, "Contoso App"); // AppName
+            string(&mut out, "Contoso App 2.1"); // AppVerName
+            string(&mut out, self.app_id); // AppId
+            string(&mut out, "(c) Contoso"); // AppCopyright
+            string(&mut out, "Contoso Ltd"); // AppPublisher
+            string(&mut out, "https://contoso.example"); // AppPublisherURL
+            string(&mut out, ""); // AppSupportPhone
+            string(&mut out, ""); // AppSupportURL
+            string(&mut out, ""); // AppUpdatesURL
+            string(&mut out, "2.1.0"); // AppVersion
+            string(&mut out, self.default_dir_name); // DefaultDirName
+            string(&mut out, "Contoso"); // DefaultGroupName
+            string(&mut out, "setup"); // OutputBaseFilename
+            string(&mut out, "{app}"); // UninstallFilesDir
+            string(&mut out, ""); // UninstallDisplayName
+            string(&mut out, ""); // UninstallDisplayIcon
+            string(&mut out, "ContosoMutex"); // AppMutex
+            string(&mut out, ""); // DefaultUserInfoName
+            string(&mut out, ""); // DefaultUserInfoOrg
+            string(&mut out, ""); // DefaultUserInfoSerial
+            string(&mut out, ""); // AppReadmeFile
+            string(&mut out, ""); // AppContact
+            string(&mut out, ""); // AppComments
+            string(&mut out, ""); // AppModifyPath
+            string(&mut out, "yes"); // CreateUninstallRegKey
+            string(&mut out, "yes"); // Uninstallable
+            string(&mut out, "*.exe,*.dll"); // CloseApplicationsFilter
+            if v >= (5, 5, 6) {
+                string(&mut out, ""); // SetupMutex
+            }
+            if v >= (5, 6, 1) {
+                string(&mut out, "no"); // ChangesEnvironment
+                string(&mut out, "no"); // ChangesAssociations
+            }
+            if v >= (6, 3, 0) {
+                string(&mut out, "x64compatible"); // ArchitecturesAllowed
+                string(&mut out, "x64compatible"); // ArchitecturesInstallIn64BitMode
+            }
+            if v >= (6, 4, 0) {
+                string(&mut out, ""); // CloseApplicationsFilterExcludes
+            }
+            if v >= (6, 5, 0) {
+                string(&mut out, ""); // SevenZipLibraryName
+            }
+            binary(&mut out, b"license"); // LicenseFile
+            binary(&mut out, b""); // InfoBeforeFile
+            binary(&mut out, b""); // InfoAfterFile
+            binary(&mut out, b"IFPS"); // Compiled [Code]
+            if !self.unicode {
+                out.extend([0u8; 32]); // LeadBytes
+            }
+
+            let counts = if v >= (6, 5, 0) { 17 } else { 16 };
+            for i in 0..counts {
+                out.extend((i as u32 % 3).to_le_bytes());
+            }
+            out.extend([0u8; 20]); // MinVersion, OnlyBelowVersion
+            out.extend([0xffu8; 8]); // BackColor, BackColor2
+            if v < (5, 5, 7) {
+                out.extend([0u8; 4]); // WizardImageBackColor
+            }
+            if v >= (6, 0, 0) {
+                out.push(1); // WizardStyle
+                out.extend(100u32.to_le_bytes()); // WizardSizePercentX
+                out.extend(120u32.to_le_bytes()); // WizardSizePercentY
+            }
+            if v >= (5, 5, 7) {
+                out.push(0); // WizardImageAlphaFormat
+            }
+            if !self.encryption_header {
+                let password_len = if v >= (6, 4, 0) { 48 } else { 28 };
+                out.extend(vec![0xaau8; password_len]);
+            }
+            out.extend(0i64.to_le_bytes()); // ExtraDiskSpaceRequired
+            out.extend(self.slices_per_disk.to_le_bytes());
+            out.push(0); // UninstallLogMode
+            out.push(0); // DirExistsWarning
+            out.push(self.privileges);
+            if v >= (5, 7, 0) {
+                out.push(self.overrides);
+            }
+            out.extend([0u8; 64]); // Options and the rest of the header
+            out
+        }
+
+        /// The setup data id and an uncompressed header block, as found at the header offset.
+        fn file(&self) -> Vec<u8> {
+            let (a, b, c) = self.version;
+            let id = format!("Inno Setup Setup Data ({}.{}.{}){}", a, b, c, if self.unicode { " (u)" } else { "" });
+            let mut out = id.into_bytes();
+            out.resize(SETUP_DATA_ID_LEN, 0);
+            if self.encryption_header {
+                let settings = [0x5au8; 49];
+                out.extend(crc32(&settings).to_le_bytes());
+                out.extend(settings);
+            }
+
+            let header = self.header();
+            let mut stored = Vec::new();
+            for chunk in header.chunks(CHUNK_LEN) {
+                stored.extend(crc32(chunk).to_le_bytes());
+                stored.extend(chunk);
+            }
+            let mut block = (stored.len() as u32).to_le_bytes().to_vec();
+            block.push(0);
+            out.extend(crc32(&block).to_le_bytes());
+            out.extend(block);
+            out.extend(stored);
+            out
+        }
+
+        fn read(&self) -> Option<InnoInfo> {
+            read_setup_data(&mut Cursor::new(self.file()), 0)
+        }
+    }
+
+    #[test]
+    fn versions_come_from_the_setup_data_id() {
+        assert_eq!(parse_version("Inno Setup Setup Data (5.5.7) (u)"), Some((5, 5, 7)));
+        assert_eq!(parse_version("Inno Setup Setup Data (6.4.0.1)"), Some((6, 4, 0)));
+        assert_eq!(parse_version("Inno Setup Setup Data (6.0)"), Some((6, 0, 0)));
+        assert_eq!(parse_version("Inno Setup Setup Data (6)"), None);
+        assert_eq!(parse_version("Inno Setup Setup Data"), None);
+        assert_eq!(parse_version("Inno Setup Setup Data (x.y.z)"), None);
+    }
+
+    #[test]
+    fn install_location_resolves_known_constants() {
+        assert_eq!(
+            install_location("{autopf}\\Contoso", Some("machine")).as_deref(),
+            Some("%ProgramFiles%\\Contoso")
+        );
+        assert_eq!(
+            install_location("{autopf}\\Contoso", Some("user")).as_deref(),
+            Some("%LocalAppData%\\Programs\\Contoso")
+        );
+        assert_eq!(install_location("{autopf}\\Contoso", None), None);
+        assert_eq!(
+            install_location("{commonpf32}\\Contoso", None).as_deref(),
+            Some("%ProgramFiles(x86)%\\Contoso")
+        );
+        assert_eq!(
+            install_location("{PF}\\Contoso", Some("user")).as_deref(),
+            Some("%ProgramFiles%\\Contoso")
+        );
+        assert_eq!(install_location("{app}\\bin", Some("machine")), None);
+        assert_eq!(install_location("{pf}\\{code:GetDir}", None), None);
+    }
+
+    #[test]
+    fn reads_ansi_and_unicode_5_5_headers() {
+        for unicode in [false, true] {
+            let setup = Setup {
+                unicode,
+                ..Setup::new((5, 5, 9))
+            };
+            let info = setup.read().unwrap();
+            assert_eq!(info.setup_version, "5.5.9");
+            assert_eq!(info.app_name.as_deref(), Some("Contoso App"));
+            assert_eq!(info.app_publisher.as_deref(), Some("Contoso Ltd"));
+            assert_eq!(info.app_version.as_deref(), Some("2.1.0"));
+            assert_eq!(
+                info.product_code.as_deref(),
+                Some("{8F2C2A41-6E5B-4C1D-9D77-0C2E7B1A9F10}_is1")
+            );
+            assert_eq!(info.privileges_required.as_deref(), Some("admin"));
+            assert!(!info.privileges_override_allowed);
+            assert_eq!(info.scope.as_deref(), Some("machine"));
+            assert_eq!(info.default_install_location.as_deref(), Some("%ProgramFiles%\\Contoso App"));
+        }
+    }
+
+    #[test]
+    fn reads_6_0_to_6_3_headers() {
+        for version in [(6, 0, 5), (6, 2, 2), (6, 3, 3)] {
+            let setup = Setup {
+                privileges: 3,
+                ..Setup::new(version)
+            };
+            let info = setup.read().unwrap();
+            assert_eq!(info.privileges_required.as_deref(), Some("lowest"), "{:?}", version);
+            assert_eq!(info.scope.as_deref(), Some("user"));
+            assert_eq!(
+                info.default_install_location.as_deref(),
+                Some("%LocalAppData%\\Programs\\Contoso App")
+            );
+        }
+    }
+
+    #[test]
+    fn reads_6_4_and_later_headers() {
+        let setups = [
+            Setup::new((6, 4, 3)),
+            Setup::new((6, 5, 0)),
+            Setup {
+                encryption_header: true,
+                ..Setup::new((6, 5, 1))
+            },
+        ];
+        for setup in setups {
+            let setup = Setup {
+                overrides: OVERRIDE_COMMAND_LINE | OVERRIDE_DIALOG,
+                ..setup
+            };
+            let info = setup.read().unwrap();
+            assert_eq!(info.app_name.as_deref(), Some("Contoso App"), "{:?}", setup.version);
+            assert_eq!(info.privileges_required.as_deref(), Some("admin"));
+            assert!(info.privileges_override_allowed);
+            assert_eq!(info.scope, None);
+            assert_eq!(info.default_install_location, None);
+        }
+    }
+
+    #[test]
+    fn misread_fixed_fields_give_no_privileges() {
+        let setup = Setup {
+            slices_per_disk: 0,
+            ..Setup::new((6, 2, 2))
+        };
+        let info = setup.read().unwrap();
+        assert_eq!(info.privileges_required, None);
+        assert_eq!(info.scope, None);
+        assert_eq!(info.app_name.as_deref(), Some("Contoso App"));
+
+        let setup = Setup {
+            privileges: 4,
+            ..Setup::new((6, 2, 2))
+        };
+        assert_eq!(setup.read().unwrap().privileges_required, None);
+    }
+
+    #[test]
+    fn damaged_setup_data_is_rejected() {
+        let setup = Setup::new((6, 2, 2));
+        let file = setup.file();
+        let block = SETUP_DATA_ID_LEN;
+
+        let mut bad_crc = file.clone();
+        bad_crc[block + 4] ^= 1;
+        assert!(read_setup_data(&mut Cursor::new(bad_crc), 0).is_none());
+        let truncated = file[..file.len() / 2].to_vec();
+        assert!(read_setup_data(&mut Cursor::new(truncated), 0).is_none());
+        let mut not_inno = file;
+        not_inno[..4].copy_from_slice(b"Nope");
+        assert!(read_setup_data(&mut Cursor::new(not_inno), 0).is_none());
+        assert!(read_setup_data(&mut Cursor::new(Vec::new()), 0).is_none());
+    }
+}
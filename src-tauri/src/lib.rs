@@ -1,22 +1,78 @@
+mod archive;
+mod associations;
+mod authenticode;
+mod burn;
+mod cab;
+mod cache;
+mod checksums;
+mod deps;
+mod fingerprint;
 mod hash;
+mod icons;
+mod inno;
+mod msi;
+mod msix;
+mod pe;
+mod sparse;
+mod version_info;
 mod yaml_generator;
 mod github;
 
+use tauri::{AppHandle, Emitter};
 use yaml_generator::YamlFile;
 
+fn hash_job(app: &AppHandle, job_id: String) -> Result<hash::HashJob, String> {
+    let app = app.clone();
+    hash::HashJob::new(job_id, move |progress| {
+        let _ = app.emit("hash-progress", progress);
+    })
+}
+
+#[tauri::command]
+async fn download_and_hash(
+    app: AppHandle,
+    url: String,
+    job_id: String,
+    token: Option<String>,
+) -> Result<hash::HashResult, String> {
+    let job = hash_job(&app, job_id)?;
+    hash::download_and_hash(url, token.as_deref(), &job).await
+}
+
+#[tauri::command]
+async fn hash_local_file(
+    app: AppHandle,
+    path: String,
+    job_id: String,
+) -> Result<hash::HashResult, String> {
+    let job = hash_job(&app, job_id)?;
+    hash::hash_local_file(path, job).await
+}
+
+#[tauri::command]
+async fn hash_batch(
+    app: AppHandle,
+    items: Vec<hash::HashRequest>,
+    token: Option<String>,
+) -> Result<Vec<hash::BatchHashResult>, String> {
+    Ok(hash::hash_batch(items, token.as_deref(), |job_id| hash_job(&app, job_id)).await)
+}
+
 #[tauri::command]
-async fn download_and_hash(url: String) -> Result<hash::HashResult, String> {
-    hash::download_and_hash(url).await
+fn cancel_hash_job(job_id: String) -> bool {
+    hash::cancel_job(&job_id)
 }
 
 #[tauri::command]
-fn hash_local_file(path: String) -> Result<hash::HashResult, String> {
-    hash::hash_local_file(&path)
+async fn clear_hash_cache() -> Result<(), String> {
+    tokio::task::spawn_blocking(cache::clear)
+        .await
+        .map_err(|e| format!("Cache task failed: {}", e))?
 }
 
 #[tauri::command]
 fn generate_yaml(manifest: yaml_generator::ManifestData) -> Result<Vec<YamlFile>, String> {
-    Ok(yaml_generator::generate_yaml(&manifest))
+    yaml_generator::generate_yaml(&manifest)
 }
 
 #[tauri::command]
@@ -164,6 +220,9 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             download_and_hash,
             hash_local_file,
+            hash_batch,
+            cancel_hash_job,
+            clear_hash_cache,
             generate_yaml,
             save_yaml_files,
             fetch_existing_manifest,
@@ -0,0 +1,612 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use crate::authenticode::{self, SignatureInfo};
+use crate::pe::{le_u16, le_u32};
+use crate::associations::{normalize_file_extension, normalize_protocol};
+
+const MIME_CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz._";
+
+const MSITYPE_VALID: u16 = 0x0100;
+const MSITYPE_STRING: u16 = 0x0800;
+const MSITYPE_NULLABLE: u16 = 0x1000;
+
+/// `Registry.Root` value for `HKEY_CLASSES_ROOT`; other roots go through `Software\Classes`.
+const ROOT_CLASSES: &str = "0";
+const CLASSES_KEY: &str = "software\\classes\\";
+
+const PID_TEMPLATE: u32 = 7;
+const VT_LPSTR: u32 = 30;
+
+/// Values read from the `Property` table and the summary information stream.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MsiInfo {
+    pub product_code: Option<String>,
+    pub upgrade_code: Option<String>,
+    pub product_version: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product_name: Option<String>,
+    pub all_users: Option<String>,
+    /// `machine` for ALLUSERS=1, `user` for per-user packages, none for dual-mode ones.
+    pub scope: Option<String>,
+    pub architecture: Option<String>,
+    pub languages: Vec<String>,
+    /// From the `Extension` table and `.ext` class keys in the `Registry` table.
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    /// Class keys the `Registry` table marks with a `URL Protocol` value.
+    #[serde(default)]
+    pub protocols: Vec<String>,
+}
+
+pub type Row = HashMap<String, String>;
+
+struct Column {
+    name: String,
+    kind: u16,
+}
+
+/// Read-only view over the tables of an MSI (OLE compound file) database.
+pub struct MsiDatabase<F> {
+    cfb: cfb::CompoundFile<F>,
+    strings: Vec<String>,
+    long_refs: bool,
+    columns: HashMap<String, Vec<Column>>,
+}
+
+fn mime_index(c: char) -> Option<u32> {
+    MIME_CHARS.iter().position(|&m| m as char == c).map(|i| i as u32)
+}
+
+/// Encodes a stream name the way msi.dll stores it in the compound file.
+fn encode_stream_name(name: &str, is_table: bool) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    if is_table {
+        out.push('\u{4840}');
+    }
+    let mut i = 0;
+    while i < chars.len() {
+        let Some(first) = mime_index(chars[i]) else {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        };
+        match chars.get(i + 1).and_then(|&c| mime_index(c)) {
+            Some(second) => {
+                out.extend(char::from_u32(0x3800 + (second << 6) + first));
+                i += 2;
+            }
+            None => {
+                out.extend(char::from_u32(0x4800 + first));
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn decode_string(bytes: &[u8], codepage: u32) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) if codepage == 65001 => String::from_utf8_lossy(bytes).to_string(),
+        // Legacy ANSI code pages: Latin-1 is right for the common Western ones
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+impl<F: Read + Seek> MsiDatabase<F> {
+    pub fn open(inner: F) -> Option<Self> {
+        let cfb = cfb::CompoundFile::open(inner).ok()?;
+        let mut db = MsiDatabase {
+            cfb,
+            strings: Vec::new(),
+            long_refs: false,
+            columns: HashMap::new(),
+        };
+        db.load_strings()?;
+        db.load_columns()?;
+        Some(db)
+    }
+
+    fn read_stream(&mut self, name: &str, is_table: bool) -> Option<Vec<u8>> {
+        let path = format!("/{}", encode_stream_name(name, is_table));
+        let mut stream = self.cfb.open_stream(&path).ok()?;
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).ok()?;
+        Some(data)
+    }
+
+    fn load_strings(&mut self) -> Option<()> {
+        let pool = self.read_stream("_StringPool", true)?;
+        let data = self.read_stream("_StringData", true)?;
+
+        let header = le_u32(&pool, 0)?;
+        self.long_refs = header & 0x8000_0000 != 0;
+        let codepage = header & 0x7fff_ffff;
+
+        // String ids are 1-based; index 0 stands for null
+        self.strings.push(String::new());
+        let entries = pool.len() / 4;
+        let mut offset = 0usize;
+        let mut i = 1;
+        while i < entries {
+            let len = le_u16(&pool, i * 4)? as usize;
+            let refs = le_u16(&pool, i * 4 + 2)?;
+            if len == 0 && refs == 0 {
+                self.strings.push(String::new());
+                i += 1;
+                continue;
+            }
+            // Strings over 64k are split across a null entry and the one following it
+            let len = if len == 0 {
+                let low = le_u16(&pool, (i + 1) * 4)? as usize;
+                let high = le_u16(&pool, (i + 1) * 4 + 2)? as usize;
+                i += 2;
+                (high << 16) | low
+            } else {
+                i += 1;
+                len
+            };
+            let bytes = data.get(offset..offset + len)?;
+            self.strings.push(decode_string(bytes, codepage));
+            offset += len;
+        }
+        Some(())
+    }
+
+    fn string_ref_size(&self) -> usize {
+        if self.long_refs { 3 } else { 2 }
+    }
+
+    fn read_ref(&self, buf: &[u8], offset: usize, size: usize) -> Option<u32> {
+        let bytes = buf.get(offset..offset + size)?;
+        Some(bytes.iter().rev().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+    }
+
+    fn load_columns(&mut self) -> Option<()> {
+        let data = self.read_stream("_Columns", true)?;
+        let sr = self.string_ref_size();
+        let row_size = sr * 2 + 4;
+        let rows = data.len() / row_size;
+
+        let mut found: Vec<(String, u16, String, u16)> = Vec::with_capacity(rows);
+        for r in 0..rows {
+            let table = self.read_ref(&data, r * sr, sr)?;
+            let number = le_u16(&data, rows * sr + r * 2)? ^ 0x8000;
+            let name = self.read_ref(&data, rows * (sr + 2) + r * sr, sr)?;
+            let kind = le_u16(&data, rows * (2 * sr + 2) + r * 2)? ^ 0x8000;
+            found.push((
+                self.string(table)?.to_string(),
+                number,
+                self.string(name)?.to_string(),
+                kind,
+            ));
+        }
+
+        found.sort_by_key(|(table, number, _, _)| (table.clone(), *number));
+        for (table, _, name, kind) in found {
+            self.columns
+                .entry(table)
+                .or_default()
+                .push(Column { name, kind });
+        }
+        Some(())
+    }
+
+    fn string(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize).map(|s| s.as_str())
+    }
+
+    fn column_size(&self, kind: u16) -> usize {
+        let is_binary = kind & !MSITYPE_NULLABLE == MSITYPE_STRING | MSITYPE_VALID;
+        if is_binary {
+            2
+        } else if kind & MSITYPE_STRING != 0 {
+            self.string_ref_size()
+        } else if kind & 0xff == 4 {
+            4
+        } else {
+            2
+        }
+    }
+
+    /// Reads every row of `table`; null cells are left out of the row map.
+    pub fn table(&mut self, table: &str) -> Option<Vec<Row>> {
+        let data = self.read_stream(table, true)?;
+        let columns = self.columns.get(table)?;
+        let sizes: Vec<usize> = columns.iter().map(|c| self.column_size(c.kind)).collect();
+        let row_size: usize = sizes.iter().sum();
+        if row_size == 0 {
+            return None;
+        }
+        let rows = data.len() / row_size;
+
+        let mut result = vec![Row::new(); rows];
+        let mut column_start = 0;
+        for (column, &size) in columns.iter().zip(&sizes) {
+            for (r, row) in result.iter_mut().enumerate() {
+                let raw = self.read_ref(&data, column_start + r * size, size)?;
+                if raw == 0 {
+                    continue;
+                }
+                let value = if column.kind & MSITYPE_STRING != 0 {
+                    match self.string(raw) {
+                        Some(s) => s.to_string(),
+                        None => continue,
+                    }
+                } else if size == 4 {
+                    ((raw ^ 0x8000_0000) as i32).to_string()
+                } else {
+                    ((raw as u16 ^ 0x8000) as i16).to_string()
+                };
+                row.insert(column.name.clone(), value);
+            }
+            column_start += rows * size;
+        }
+        Some(result)
+    }
+
+    /// The `Template` summary property, e.g. `x64;1033`.
+    pub fn template(&mut self) -> Option<String> {
+        let mut stream = self.cfb.open_stream("/\u{5}SummaryInformation").ok()?;
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).ok()?;
+
+        let section = le_u32(&data, 44)? as usize;
+        let count = le_u32(&data, section + 4)? as usize;
+        (0..count).find_map(|i| {
+            let entry = section + 8 + i * 8;
+            if le_u32(&data, entry)? != PID_TEMPLATE {
+                return None;
+            }
+            let value = section + le_u32(&data, entry + 4)? as usize;
+            if le_u32(&data, value)? != VT_LPSTR {
+                return None;
+            }
+            let len = le_u32(&data, value + 4)? as usize;
+            let bytes = data.get(value + 8..value + 8 + len)?;
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+        })
+    }
+}
+
+fn template_architecture(platform: &str) -> Option<&'static str> {
+    match platform.trim().to_ascii_lowercase().as_str() {
+        "intel" => Some("x86"),
+        "x64" | "amd64" => Some("x64"),
+        "arm64" => Some("arm64"),
+        "arm" => Some("arm"),
+        _ => None,
+    }
+}
+
+/// Path of a `Registry` row's key below the classes root, e.g. `.txt\OpenWithProgids`.
+fn registry_class(row: &Row) -> Option<&str> {
+    let key = row.get("Key")?;
+    let class = if row.get("Root").map(|r| r.as_str()) == Some(ROOT_CLASSES) {
+        key.as_str()
+    } else {
+        let prefix = key.get(..CLASSES_KEY.len())?;
+        if !prefix.eq_ignore_ascii_case(CLASSES_KEY) {
+            return None;
+        }
+        &key[CLASSES_KEY.len()..]
+    };
+    Some(class)
+}
+
+/// File extensions and URL protocols the package registers.
+fn associations<F: Read + Seek>(db: &mut MsiDatabase<F>) -> (Vec<String>, Vec<String>) {
+    let mut extensions: Vec<String> = db
+        .table("Extension")
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|row| normalize_file_extension(row.get("Extension")?))
+        .collect();
+    let mut protocols = Vec::new();
+    for row in db.table("Registry").unwrap_or_default() {
+        let Some(class) = registry_class(&row) else {
+            continue;
+        };
+        let (name, subkey) = class.split_once('\\').unwrap_or((class, ""));
+        // Formatted names such as `[ProgId]` are only known at install time
+        if name.contains('[') {
+            continue;
+        }
+        if name.starts_with('.') {
+            extensions.extend(normalize_file_extension(name));
+        } else if subkey.is_empty()
+            && row.get("Name").is_some_and(|n| n.eq_ignore_ascii_case("URL Protocol"))
+        {
+            protocols.extend(normalize_protocol(name));
+        }
+    }
+    for list in [&mut extensions, &mut protocols] {
+        let mut seen = HashSet::new();
+        list.retain(|v| seen.insert(v.clone()));
+    }
+    (extensions, protocols)
+}
+
+pub fn analyze<F: Read + Seek>(inner: F) -> Option<MsiInfo> {
+    let mut db = MsiDatabase::open(inner)?;
+
+    let properties: HashMap<String, String> = db
+        .table("Property")?
+        .into_iter()
+        .filter_map(|mut row| Some((row.remove("Property")?, row.remove("Value")?)))
+        .collect();
+    let prop = |name: &str| properties.get(name).filter(|v| !v.is_empty()).cloned();
+
+    let all_users = prop("ALLUSERS");
+    let scope = match all_users.as_deref() {
+        Some("1") => Some("machine".to_string()),
+        Some("2") if prop("MSIINSTALLPERUSER").as_deref() == Some("1") => {
+            Some("user".to_string())
+        }
+        Some(_) => None,
+        None => Some("user".to_string()),
+    };
+
+    let template = db.template();
+    let (file_extensions, protocols) = associations(&mut db);
+    let (platform, languages) = template
+        .as_deref()
+        .map(|t| t.split_once(';').unwrap_or((t, "")))
+        .unzip();
+
+    Some(MsiInfo {
+        product_code: prop("ProductCode"),
+        upgrade_code: prop("UpgradeCode"),
+        product_version: prop("ProductVersion"),
+        manufacturer: prop("Manufacturer"),
+        product_name: prop("ProductName"),
+        all_users,
+        scope,
+        architecture: platform
+            .and_then(template_architecture)
+            .map(|a| a.to_string()),
+        languages: languages
+            .unwrap_or_default()
+            .split(',')
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        file_extensions,
+        protocols,
+    })
+}
+
+/// Reads the Authenticode signature stored in the `\u{5}DigitalSignature` stream.
+pub fn signature<F: Read + Seek>(inner: F) -> Option<SignatureInfo> {
+    let mut cfb = cfb::CompoundFile::open(inner).ok()?;
+    let Ok(mut stream) = cfb.open_stream("/\u{5}DigitalSignature") else {
+        return Some(SignatureInfo::unsigned());
+    };
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data).ok()?;
+    Some(authenticode::parse_pkcs7(&data).unwrap_or_else(SignatureInfo::unreadable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    const STRING: u16 = MSITYPE_VALID | MSITYPE_STRING | MSITYPE_NULLABLE | 0xff;
+    const INT16: u16 = MSITYPE_VALID | MSITYPE_NULLABLE | 2;
+    const INT32: u16 = MSITYPE_VALID | MSITYPE_NULLABLE | 4;
+
+    type Table<'a> = (&'a str, &'a [(&'a str, u16)], &'a [&'a [&'a str]]);
+
+    /// Builds an MSI database holding `tables`; empty cells are null.
+    fn database(tables: &[Table]) -> Vec<u8> {
+        database_with(tables, false)
+    }
+
+    /// Same, with 3-byte string references as in databases of over 64k strings.
+    fn database_with(tables: &[Table], long_refs: bool) -> Vec<u8> {
+        let mut strings: Vec<String> = Vec::new();
+        let mut id = |s: &str| -> u32 {
+            if s.is_empty() {
+                return 0;
+            }
+            let index = strings.iter().position(|v| v == s).unwrap_or_else(|| {
+                strings.push(s.to_string());
+                strings.len() - 1
+            });
+            index as u32 + 1
+        };
+
+        let string_ref = |id: u32| id.to_le_bytes()[..if long_refs { 3 } else { 2 }].to_vec();
+
+        let mut streams = Vec::new();
+        let (mut col_tables, mut col_numbers, mut col_names, mut col_kinds) = (vec![], vec![], vec![], vec![]);
+        for (name, columns, rows) in tables {
+            let mut data = Vec::new();
+            for (c, (column, kind)) in columns.iter().enumerate() {
+                col_tables.extend(string_ref(id(name)));
+                col_numbers.extend(((c as u16 + 1) ^ 0x8000).to_le_bytes());
+                col_names.extend(string_ref(id(column)));
+                col_kinds.extend((kind ^ 0x8000).to_le_bytes());
+                for row in rows.iter() {
+                    let cell = row[c];
+                    if kind & MSITYPE_STRING != 0 {
+                        data.extend(string_ref(id(cell)));
+                    } else if kind & 0xff == 4 {
+                        let raw = if cell.is_empty() { 0 } else { cell.parse::<i32>().unwrap() as u32 ^ 0x8000_0000 };
+                        data.extend(raw.to_le_bytes());
+                    } else {
+                        let raw = if cell.is_empty() { 0 } else { cell.parse::<i16>().unwrap() as u16 ^ 0x8000 };
+                        data.extend(raw.to_le_bytes());
+                    }
+                }
+            }
+            streams.push((name.to_string(), data));
+        }
+        let columns = [col_tables, col_numbers, col_names, col_kinds].concat();
+        streams.push(("_Columns".to_string(), columns));
+
+        let flag = if long_refs { 0x8000_0000 } else { 0 };
+        let mut pool = (65001u32 | flag).to_le_bytes().to_vec();
+        for s in &strings {
+            if s.len() > 0xffff {
+                // A null entry, then the length over the whole next entry
+                pool.extend([0, 0, 1, 0]);
+                pool.extend((s.len() as u32).to_le_bytes());
+            } else {
+                pool.extend((s.len() as u16).to_le_bytes());
+                pool.extend(1u16.to_le_bytes());
+            }
+        }
+        streams.push(("_StringPool".to_string(), pool));
+        streams.push(("_StringData".to_string(), strings.concat().into_bytes()));
+
+        let mut cfb = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in streams {
+            let path = format!("/{}", encode_stream_name(&name, true));
+            cfb.create_stream(&path).unwrap().write_all(&data).unwrap();
+        }
+        cfb.flush().unwrap();
+        cfb.into_inner().into_inner()
+    }
+
+    /// Adds a summary information stream whose only property is `Template`.
+    fn with_template(bytes: Vec<u8>, template: &str) -> Vec<u8> {
+        let mut value = template.as_bytes().to_vec();
+        value.push(0);
+        let mut section = Vec::new();
+        section.extend((8 + 8 + 8 + value.len() as u32).to_le_bytes());
+        section.extend(1u32.to_le_bytes());
+        section.extend(PID_TEMPLATE.to_le_bytes());
+        section.extend(16u32.to_le_bytes());
+        section.extend(VT_LPSTR.to_le_bytes());
+        section.extend((value.len() as u32).to_le_bytes());
+        section.extend(value);
+        let mut stream = vec![0u8; 44];
+        stream[..2].copy_from_slice(&0xfffeu16.to_le_bytes());
+        stream[24..28].copy_from_slice(&1u32.to_le_bytes());
+        stream.extend(48u32.to_le_bytes());
+        stream.extend(section);
+
+        let mut cfb = cfb::CompoundFile::open(Cursor::new(bytes)).unwrap();
+        cfb.create_stream("/\u{5}SummaryInformation").unwrap().write_all(&stream).unwrap();
+        cfb.flush().unwrap();
+        cfb.into_inner().into_inner()
+    }
+
+    fn row(values: &[(&str, &str)]) -> Row {
+        values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn registry_class_is_relative_to_classes_root() {
+        let class = |root: &str, key: &str| registry_class(&row(&[("Root", root), ("Key", key)])).map(str::to_string);
+        assert_eq!(class("0", ".txt\\OpenWithProgids").as_deref(), Some(".txt\\OpenWithProgids"));
+        assert_eq!(class("2", "SOFTWARE\\Classes\\contoso").as_deref(), Some("contoso"));
+        assert_eq!(class("-1", "Software\\Classes\\.md").as_deref(), Some(".md"));
+        assert_eq!(class("2", "Software\\Contoso\\Viewer"), None);
+        assert_eq!(class("1", "Soft"), None);
+    }
+
+    #[test]
+    fn associations_come_from_extension_and_registry_tables() {
+        let bytes = database(&[
+            ("Extension", &[("Extension", STRING), ("Component_", STRING)], &[&["txt", "Main"], &["LOG", "Main"]]),
+            (
+                "Registry",
+                &[("Registry", STRING), ("Root", INT16), ("Key", STRING), ("Name", STRING), ("Value", STRING)],
+                &[
+                    &["r1", "0", ".TXT\\OpenWithProgids", "Contoso.txt", ""],
+                    &["r2", "2", "Software\\Classes\\contoso", "URL Protocol", ""],
+                    &["r3", "2", "Software\\Classes\\contoso\\shell\\open\\command", "", "\"[#App]\" \"%1\""],
+                    &["r4", "2", "Software\\Contoso", "URL Protocol", ""],
+                    &["r5", "0", "[ProgId]", "URL Protocol", ""],
+                    &["r6", "-1", "Software\\Classes\\.md", "", "Contoso.md"],
+                ],
+            ),
+        ]);
+        let mut db = MsiDatabase::open(Cursor::new(bytes)).unwrap();
+        let (extensions, protocols) = associations(&mut db);
+        assert_eq!(extensions, ["txt", "log", "md"]);
+        assert_eq!(protocols, ["contoso"]);
+    }
+
+    const PROPERTY: &[(&str, u16)] = &[("Property", STRING), ("Value", STRING)];
+
+    #[test]
+    fn reads_properties_and_template() {
+        let bytes = database(&[(
+            "Property",
+            PROPERTY,
+            &[
+                &["ProductCode", "{8F3C2B1A-6D5E-4C7B-9A0F-1E2D3C4B5A69}"],
+                &["UpgradeCode", "{B2C3D4E5-F6A7-4B8C-9D0E-1F2A3B4C5D6E}"],
+                &["ProductVersion", "2.4.1"],
+                &["Manufacturer", "Contoso Ltd."],
+                &["ProductName", "Contoso Ünïcode"],
+                &["ALLUSERS", "2"],
+                &["MSIINSTALLPERUSER", "1"],
+                &["ARPCOMMENTS", ""],
+            ],
+        )]);
+        let info = analyze(Cursor::new(with_template(bytes, "x64;1033,1036"))).unwrap();
+        assert_eq!(info.product_code.as_deref(), Some("{8F3C2B1A-6D5E-4C7B-9A0F-1E2D3C4B5A69}"));
+        assert_eq!(info.upgrade_code.as_deref(), Some("{B2C3D4E5-F6A7-4B8C-9D0E-1F2A3B4C5D6E}"));
+        assert_eq!(info.product_version.as_deref(), Some("2.4.1"));
+        assert_eq!(info.manufacturer.as_deref(), Some("Contoso Ltd."));
+        assert_eq!(info.product_name.as_deref(), Some("Contoso Ünïcode"));
+        assert_eq!(info.all_users.as_deref(), Some("2"));
+        assert_eq!(info.scope.as_deref(), Some("user"));
+        assert_eq!(info.architecture.as_deref(), Some("x64"));
+        assert_eq!(info.languages, ["1033", "1036"]);
+
+        // No ALLUSERS is a per-user package; no summary stream, no architecture
+        let info = analyze(Cursor::new(database(&[("Property", PROPERTY, &[&["ProductName", "Viewer"]])]))).unwrap();
+        assert_eq!(info.scope.as_deref(), Some("user"));
+        assert_eq!(info.architecture, None);
+        assert!(info.languages.is_empty());
+    }
+
+    #[test]
+    fn reads_long_references_and_strings() {
+        let long = "x".repeat(70_000);
+        let bytes = database_with(
+            &[
+                ("Property", PROPERTY, &[&["ALLUSERS", "1"], &["ARPREADME", &long]]),
+                ("Media", &[("DiskId", INT16), ("LastSequence", INT32), ("Cabinet", STRING)], &[&["1", "-70000", "#data.cab"], &["2", "", ""]]),
+            ],
+            true,
+        );
+        let mut db = MsiDatabase::open(Cursor::new(bytes)).unwrap();
+        assert!(db.long_refs);
+        let properties = db.table("Property").unwrap();
+        assert_eq!(properties[1]["Value"].len(), 70_000);
+        let media = db.table("Media").unwrap();
+        assert_eq!(media[0], row(&[("DiskId", "1"), ("LastSequence", "-70000"), ("Cabinet", "#data.cab")]));
+        assert_eq!(media[1], row(&[("DiskId", "2")]));
+        assert_eq!(db.table("File"), None);
+    }
+
+    #[test]
+    fn damaged_databases_are_not_read() {
+        let bytes = database(&[("Property", PROPERTY, &[&["ProductName", "Viewer"]])]);
+        assert!(analyze(Cursor::new(bytes[..bytes.len() / 2].to_vec())).is_none());
+        assert!(analyze(Cursor::new(b"MZ\x90\0".to_vec())).is_none());
+
+        // A string pool naming more data than `_StringData` holds
+        let mut cfb = cfb::CompoundFile::open(Cursor::new(bytes)).unwrap();
+        let path = format!("/{}", encode_stream_name("_StringData", true));
+        cfb.create_stream(&path).unwrap().write_all(b"Prop").unwrap();
+        cfb.flush().unwrap();
+        assert!(MsiDatabase::open(Cursor::new(cfb.into_inner().into_inner())).is_none());
+
+        // Without a Property table there is nothing to report
+        assert!(analyze(Cursor::new(database(&[("Media", &[("DiskId", INT16)], &[&["1"]])]))).is_none());
+        assert_eq!(decode_string(b"Caf\xe9", 1252), "Café");
+    }
+}
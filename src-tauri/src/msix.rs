@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+
+use crate::icons::{self, IconInfo};
+use crate::associations::{normalize_file_extension, normalize_protocol};
+
+/// Alphabet used by Windows when encoding the publisher id of a package family name.
+const PUBLISHER_ID_ALPHABET: &[u8] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+/// Package identity and requirements declared in `AppxManifest.xml`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MsixInfo {
+    pub identity_name: Option<String>,
+    pub publisher: Option<String>,
+    pub version: Option<String>,
+    pub processor_architecture: Option<String>,
+    pub min_os_version: Option<String>,
+    pub package_family_name: Option<String>,
+    pub is_bundle: bool,
+    /// Packages listed by a bundle manifest; empty for single packages.
+    pub packages: Vec<BundlePackage>,
+    /// Framework packages the app declares with `PackageDependency`.
+    #[serde(default)]
+    pub package_dependencies: Vec<MsixDependency>,
+    /// Types listed by `uap:FileTypeAssociation` extensions.
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    /// Schemes declared by `uap:Protocol` extensions.
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    /// `uap5:ExecutionAlias` names without their `.exe`.
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MsixDependency {
+    pub name: String,
+    pub min_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePackage {
+    pub file_name: String,
+    pub package_type: String,
+    pub architecture: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Computes the 13-character publisher id: the first 8 bytes of the SHA256 of the
+/// UTF-16LE publisher, padded to 65 bits and written in 5-bit groups.
+pub fn publisher_id(publisher: &str) -> String {
+    let utf16: Vec<u8> = publisher
+        .encode_utf16()
+        .flat_map(|u| u.to_le_bytes())
+        .collect();
+    let digest = Sha256::digest(&utf16);
+    let bits = u64::from_be_bytes(digest[..8].try_into().unwrap_or([0; 8]));
+    let padded = (bits as u128) << 1;
+
+    (0..13)
+        .map(|i| PUBLISHER_ID_ALPHABET[((padded >> (60 - 5 * i)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+pub fn package_family_name(name: &str, publisher: &str) -> String {
+    format!("{}_{}", name, publisher_id(publisher))
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Option<Vec<u8>> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+pub fn signature_hash<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Option<String> {
+    let mut signature = archive.by_name("AppxSignature.p7x").ok()?;
+
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 65536];
+    loop {
+        let n = signature.read(&mut buf).ok()?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Some(format!("{:X}", hasher.finalize()))
+}
+
+/// Parses either `AppxManifest.xml` or a bundle's `AppxBundleManifest.xml`.
+pub fn parse_manifest(xml: &str) -> Option<MsixInfo> {
+    let doc = roxmltree::Document::parse(xml).ok()?;
+    let identity = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "Identity")?;
+
+    let identity_name = identity.attribute("Name").map(|v| v.to_string());
+    let publisher = identity.attribute("Publisher").map(|v| v.to_string());
+    let processor_architecture = identity
+        .attribute("ProcessorArchitecture")
+        .map(|v| v.to_ascii_lowercase());
+
+    // Desktop is what winget cares about; Universal covers packages that only declare that
+    let families: Vec<_> = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "TargetDeviceFamily")
+        .collect();
+    let min_os_version = ["Windows.Desktop", "Windows.Universal"]
+        .iter()
+        .find_map(|family| {
+            families
+                .iter()
+                .find(|n| n.attribute("Name") == Some(*family))
+                .and_then(|n| n.attribute("MinVersion"))
+        })
+        .map(|v| v.to_string());
+
+    let package_family_name = match (&identity_name, &publisher) {
+        (Some(name), Some(publisher)) => Some(package_family_name(name, publisher)),
+        _ => None,
+    };
+
+    let is_bundle = doc.root_element().tag_name().name() == "Bundle";
+    let packages = doc
+        .descendants()
+        .filter(|n| is_bundle && n.tag_name().name() == "Package")
+        .filter_map(|n| {
+            Some(BundlePackage {
+                file_name: n.attribute("FileName")?.to_string(),
+                package_type: n.attribute("Type").unwrap_or("application").to_string(),
+                architecture: n.attribute("Architecture").map(|v| v.to_ascii_lowercase()),
+                version: n.attribute("Version").map(|v| v.to_string()),
+            })
+        })
+        .collect();
+    let package_dependencies = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "PackageDependency")
+        .filter_map(|n| {
+            Some(MsixDependency {
+                name: n.attribute("Name")?.to_string(),
+                min_version: n.attribute("MinVersion").map(|v| v.to_string()),
+            })
+        })
+        .collect();
+
+    let mut file_extensions = Vec::new();
+    let mut protocols = Vec::new();
+    let mut commands = Vec::new();
+    for node in doc.descendants() {
+        // ShareTarget and other extensions list file types and protocols too; only
+        // associations register the package as a handler
+        match node.tag_name().name() {
+            "FileType" if has_ancestor(node, |n| n.tag_name().name() == "FileTypeAssociation") => {
+                file_extensions.extend(node.text().and_then(normalize_file_extension))
+            }
+            "Protocol" if has_ancestor(node, |n| is_extension(n, "windows.protocol")) => {
+                protocols.extend(node.attribute("Name").and_then(normalize_protocol))
+            }
+            "ExecutionAlias" => commands.extend(node.attribute("Alias").map(alias_command)),
+            _ => continue,
+        }
+    }
+    for list in [&mut file_extensions, &mut protocols, &mut commands] {
+        let mut seen = HashSet::new();
+        list.retain(|v| seen.insert(v.clone()));
+    }
+
+    Some(MsixInfo {
+        identity_name,
+        publisher,
+        version: identity.attribute("Version").map(|v| v.to_string()),
+        processor_architecture,
+        min_os_version,
+        package_family_name,
+        is_bundle,
+        packages,
+        package_dependencies,
+        file_extensions,
+        protocols,
+        commands,
+    })
+}
+
+fn has_ancestor(node: roxmltree::Node, matches: impl Fn(roxmltree::Node) -> bool) -> bool {
+    node.ancestors().skip(1).any(matches)
+}
+
+fn is_extension(node: roxmltree::Node, category: &str) -> bool {
+    node.tag_name().name() == "Extension" && node.attribute("Category") == Some(category)
+}
+
+fn alias_command(alias: &str) -> String {
+    let alias = alias.trim();
+    let stem = alias
+        .len()
+        .checked_sub(4)
+        .filter(|&i| alias.get(i..).is_some_and(|ext| ext.eq_ignore_ascii_case(".exe")));
+    stem.map_or(alias, |i| &alias[..i]).to_string()
+}
+
+impl MsixInfo {
+    /// Distinct architectures of the application packages in a bundle, in manifest order.
+    pub fn bundle_architectures(&self) -> Vec<String> {
+        let mut archs: Vec<String> = Vec::new();
+        for package in &self.packages {
+            if package.package_type != "application" {
+                continue;
+            }
+            if let Some(ref arch) = package.architecture {
+                if !archs.contains(arch) {
+                    archs.push(arch.clone());
+                }
+            }
+        }
+        archs
+    }
+}
+
+/// Whether a zip archive is an app package or bundle, for files without a telling extension.
+pub fn is_package<R: Read + Seek>(reader: R) -> bool {
+    zip::ZipArchive::new(reader).is_ok_and(|archive| {
+        archive.index_for_name("AppxManifest.xml").is_some()
+            || archive
+                .index_for_name("AppxMetadata/AppxBundleManifest.xml")
+                .is_some()
+    })
+}
+
+/// Reads the signature hash, the manifest and the logo icons of an `.msix`/`.appx`
+/// package or bundle.
+pub fn analyze<R: Read + Seek>(reader: R) -> (Option<String>, Option<MsixInfo>, Vec<IconInfo>) {
+    let Ok(mut archive) = zip::ZipArchive::new(reader) else {
+        return (None, None, Vec::new());
+    };
+    let signature = signature_hash(&mut archive);
+    let manifest_xml = read_entry(&mut archive, "AppxManifest.xml")
+        .or_else(|| read_entry(&mut archive, "AppxMetadata/AppxBundleManifest.xml"))
+        .map(|data| String::from_utf8_lossy(&data).trim_start_matches('\u{feff}').to_string());
+    let manifest = manifest_xml.as_deref().and_then(parse_manifest);
+    // Bundles only list their packages; the logos live inside those
+    let icons = manifest_xml
+        .as_deref()
+        .filter(|_| manifest.as_ref().is_some_and(|m| !m.is_bundle))
+        .map(|xml| icons::from_package(&mut archive, xml))
+        .unwrap_or_default();
+    (signature, manifest, icons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publisher_id_matches_windows() {
+        assert_eq!(
+            publisher_id("CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US"),
+            "8wekyb3d8bbwe"
+        );
+        assert_eq!(
+            publisher_id("CN=Microsoft Windows, O=Microsoft Corporation, L=Redmond, S=Washington, C=US"),
+            "cw5n1h2txyewy"
+        );
+    }
+
+    #[test]
+    fn family_name_joins_name_and_publisher_id() {
+        assert_eq!(
+            package_family_name(
+                "Microsoft.WindowsTerminal",
+                "CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US"
+            ),
+            "Microsoft.WindowsTerminal_8wekyb3d8bbwe"
+        );
+    }
+
+    #[test]
+    fn associations_skip_share_targets() {
+        let xml = r#"<Package xmlns="http://schemas.microsoft.com/appx/manifest/foundation/windows10"
+          xmlns:uap="http://schemas.microsoft.com/appx/manifest/uap/windows10"
+          xmlns:uap5="http://schemas.microsoft.com/appx/manifest/uap/windows10/5">
+          <Identity Name="Contoso.Viewer" Publisher="CN=Contoso" Version="1.2.0.0" ProcessorArchitecture="x64" />
+          <Applications>
+            <Application Id="App">
+              <Extensions>
+                <uap:Extension Category="windows.fileTypeAssociation">
+                  <uap:FileTypeAssociation Name="images">
+                    <uap:SupportedFileTypes>
+                      <uap:FileType>.PNG</uap:FileType>
+                      <uap:FileType>.webp</uap:FileType>
+                    </uap:SupportedFileTypes>
+                  </uap:FileTypeAssociation>
+                </uap:Extension>
+                <uap:Extension Category="windows.shareTarget">
+                  <uap:ShareTarget>
+                    <uap:SupportedFileTypes>
+                      <uap:FileType>.jpg</uap:FileType>
+                    </uap:SupportedFileTypes>
+                    <uap:DataFormat>URI</uap:DataFormat>
+                  </uap:ShareTarget>
+                </uap:Extension>
+                <uap:Extension Category="windows.protocol">
+                  <uap:Protocol Name="Contoso-Viewer" />
+                </uap:Extension>
+                <uap5:Extension Category="windows.appExecutionAlias">
+                  <uap5:AppExecutionAlias>
+                    <uap5:ExecutionAlias Alias="viewer.exe" />
+                  </uap5:AppExecutionAlias>
+                </uap5:Extension>
+              </Extensions>
+            </Application>
+          </Applications>
+        </Package>"#;
+        let info = parse_manifest(xml).unwrap();
+        assert_eq!(info.file_extensions, ["png", "webp"]);
+        assert_eq!(info.protocols, ["contoso-viewer"]);
+        assert_eq!(info.commands, ["viewer"]);
+    }
+}
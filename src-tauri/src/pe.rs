@@ -0,0 +1,491 @@
+use std::io::{Read, Seek, SeekFrom};
+
+const IMAGE_FILE_MACHINE_I386: u16 = 0x014c;
+const IMAGE_FILE_MACHINE_ARM: u16 = 0x01c0;
+const IMAGE_FILE_MACHINE_ARMNT: u16 = 0x01c4;
+const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+const IMAGE_FILE_MACHINE_ARM64: u16 = 0xaa64;
+
+pub const IMAGE_FILE_DLL: u16 = 0x2000;
+pub const IMAGE_SUBSYSTEM_WINDOWS_GUI: u16 = 2;
+pub const IMAGE_SUBSYSTEM_WINDOWS_CUI: u16 = 3;
+
+const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+pub const IMAGE_DIRECTORY_ENTRY_SECURITY: usize = 4;
+const IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR: usize = 14;
+/// Guards against malformed import tables.
+const MAX_IMPORTED_DLLS: usize = 256;
+/// Resources larger than this are not read; version info, manifests and icons are far smaller.
+const MAX_RESOURCE_SIZE: u32 = 8 * 1024 * 1024;
+/// CLR metadata larger than this is not scanned.
+const MAX_CLR_METADATA: u32 = 16 * 1024 * 1024;
+
+const COMIMAGE_FLAGS_ILONLY: u32 = 0x0000_0001;
+const COMIMAGE_FLAGS_32BITREQUIRED: u32 = 0x0000_0002;
+const COMIMAGE_FLAGS_32BITPREFERRED: u32 = 0x0002_0000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DataDirectory {
+    pub rva: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub name: String,
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub raw_size: u32,
+    pub raw_offset: u32,
+}
+
+/// The parts of the DOS/COFF/optional headers the installer analysis relies on.
+#[derive(Debug, Clone)]
+pub struct PeHeader {
+    pub machine: u16,
+    /// COFF characteristics, e.g. `IMAGE_FILE_DLL`.
+    pub characteristics: u16,
+    /// `IMAGE_SUBSYSTEM_*` value: 2 for GUI applications, 3 for console ones.
+    pub subsystem: u16,
+    pub data_directories: Vec<DataDirectory>,
+    pub sections: Vec<Section>,
+}
+
+impl PeHeader {
+    pub fn data_directory(&self, index: usize) -> Option<DataDirectory> {
+        self.data_directories
+            .get(index)
+            .copied()
+            .filter(|d| d.rva != 0 && d.size != 0)
+    }
+
+    pub fn rva_to_offset(&self, rva: u32) -> Option<u64> {
+        self.sections.iter().find_map(|s| {
+            let span = s.virtual_size.max(s.raw_size);
+            if rva >= s.virtual_address && rva < s.virtual_address.saturating_add(span) {
+                let delta = rva - s.virtual_address;
+                if delta < s.raw_size {
+                    return Some(s.raw_offset as u64 + delta as u64);
+                }
+            }
+            None
+        })
+    }
+}
+
+pub fn le_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    buf.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub fn le_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    buf.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Reads `len` bytes at `offset`; lengths taken from the file are checked against its
+/// size before anything is allocated.
+pub fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let end = reader.seek(SeekFrom::End(0)).ok()?;
+    if offset.checked_add(len as u64)? > end {
+        return None;
+    }
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+pub fn read_header<R: Read + Seek>(reader: &mut R) -> Option<PeHeader> {
+    let dos = read_at(reader, 0, 64)?;
+    if &dos[..2] != b"MZ" {
+        return None;
+    }
+    let pe_offset = le_u32(&dos, 0x3c)? as u64;
+
+    let coff = read_at(reader, pe_offset, 24)?;
+    if &coff[..4] != b"PE\0\0" {
+        return None;
+    }
+    let machine = le_u16(&coff, 4)?;
+    let section_count = le_u16(&coff, 6)? as usize;
+    let optional_size = le_u16(&coff, 20)? as usize;
+    let characteristics = le_u16(&coff, 22)?;
+
+    let optional = read_at(reader, pe_offset + 24, optional_size)?;
+    let is_pe32_plus = match le_u16(&optional, 0)? {
+        0x10b => false,
+        0x20b => true,
+        _ => return None,
+    };
+    let subsystem = le_u16(&optional, 68)?;
+    let (count_offset, dirs_offset) = if is_pe32_plus { (108, 112) } else { (92, 96) };
+    let dir_count = le_u32(&optional, count_offset).unwrap_or(0).min(16) as usize;
+    let data_directories = (0..dir_count)
+        .map_while(|i| {
+            let base = dirs_offset + i * 8;
+            Some(DataDirectory {
+                rva: le_u32(&optional, base)?,
+                size: le_u32(&optional, base + 4)?,
+            })
+        })
+        .collect();
+
+    let section_table = read_at(
+        reader,
+        pe_offset + 24 + optional_size as u64,
+        section_count.min(96) * 40,
+    )?;
+    let sections = section_table
+        .chunks_exact(40)
+        .map(|s| Section {
+            name: String::from_utf8_lossy(&s[..8])
+                .trim_end_matches('\0')
+                .to_string(),
+            virtual_size: le_u32(s, 8).unwrap_or(0),
+            virtual_address: le_u32(s, 12).unwrap_or(0),
+            raw_size: le_u32(s, 16).unwrap_or(0),
+            raw_offset: le_u32(s, 20).unwrap_or(0),
+        })
+        .collect();
+
+    Some(PeHeader {
+        machine,
+        characteristics,
+        subsystem,
+        data_directories,
+        sections,
+    })
+}
+
+/// Reads the COR20 header flags of a managed (.NET) image.
+fn read_clr_flags<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<u32> {
+    let dir = header.data_directory(IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)?;
+    let offset = header.rva_to_offset(dir.rva)?;
+    let cor20 = read_at(reader, offset, 20)?;
+    le_u32(&cor20, 16)
+}
+
+/// Raw CLR metadata of a managed image, whose string heaps name the target framework
+/// and referenced assemblies.
+pub fn clr_metadata<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<Vec<u8>> {
+    let dir = header.data_directory(IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)?;
+    let cor20 = read_at(reader, header.rva_to_offset(dir.rva)?, 16)?;
+    let size = le_u32(&cor20, 12)?;
+    if size > MAX_CLR_METADATA {
+        return None;
+    }
+    read_at(reader, header.rva_to_offset(le_u32(&cor20, 8)?)?, size as usize)
+}
+
+fn read_c_string<R: Read + Seek>(reader: &mut R, offset: u64) -> Option<String> {
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buf = [0u8; 256];
+    let n = reader.read(&mut buf).ok()?;
+    let end = buf[..n].iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&buf[..end]).to_string())
+}
+
+/// Names of the DLLs the import table links against, lowercased.
+pub fn imported_dlls<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Vec<String> {
+    let Some(offset) = header
+        .data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT)
+        .and_then(|dir| header.rva_to_offset(dir.rva))
+    else {
+        return Vec::new();
+    };
+    let mut dlls = Vec::new();
+    for i in 0..MAX_IMPORTED_DLLS as u64 {
+        let Some(descriptor) = read_at(reader, offset + i * 20, 20) else {
+            break;
+        };
+        // The table ends with an all-zero descriptor
+        let Some(name_rva) = le_u32(&descriptor, 12).filter(|&rva| rva != 0) else {
+            break;
+        };
+        if let Some(name) = header
+            .rva_to_offset(name_rva)
+            .and_then(|at| read_c_string(reader, at))
+        {
+            dlls.push(name.to_ascii_lowercase());
+        }
+    }
+    dlls
+}
+
+/// Architecture as reported by the image itself, plus whether the CLR header decided it.
+///
+/// IL-only assemblies that do not require 32-bit are reported as `neutral`,
+/// regardless of the (always i386) machine field the compiler stamps on them.
+/// AnyCPU assemblies built with "Prefer 32-bit" run as 32-bit processes, so they
+/// are `x86` rather than something winget would also offer on x64 and arm64.
+pub fn architecture<R: Read + Seek>(
+    reader: &mut R,
+    header: &PeHeader,
+) -> Option<(&'static str, bool)> {
+    if let Some(flags) = read_clr_flags(reader, header) {
+        let il_only = flags & COMIMAGE_FLAGS_ILONLY != 0;
+        let requires_32 = flags & COMIMAGE_FLAGS_32BITREQUIRED != 0;
+        let prefers_32 = flags & COMIMAGE_FLAGS_32BITPREFERRED != 0;
+        if header.machine == IMAGE_FILE_MACHINE_I386 && il_only {
+            let arch = if requires_32 || prefers_32 { "x86" } else { "neutral" };
+            return Some((arch, true));
+        }
+    }
+
+    let arch = match header.machine {
+        IMAGE_FILE_MACHINE_I386 => "x86",
+        IMAGE_FILE_MACHINE_AMD64 => "x64",
+        IMAGE_FILE_MACHINE_ARM64 => "arm64",
+        IMAGE_FILE_MACHINE_ARM | IMAGE_FILE_MACHINE_ARMNT => "arm",
+        _ => return None,
+    };
+    Some((arch, false))
+}
+
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+/// Guards against malformed or cyclic resource trees.
+const MAX_RESOURCE_ENTRIES: usize = 4096;
+
+pub const RT_ICON: u32 = 3;
+pub const RT_GROUP_ICON: u32 = 14;
+pub const RT_VERSION: u32 = 16;
+pub const RT_MANIFEST: u32 = 24;
+
+/// Identifies a resource directory entry either by numeric id or by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceName {
+    Id(u32),
+    Name(String),
+}
+
+/// A leaf of the resource tree, located by file offset.
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub name: ResourceName,
+    pub language: u32,
+    pub offset: u64,
+    pub size: u32,
+}
+
+fn read_directory_entries<R: Read + Seek>(
+    reader: &mut R,
+    section_offset: u64,
+    dir_offset: u32,
+) -> Vec<(ResourceName, u32)> {
+    let Some(dir) = read_at(reader, section_offset + dir_offset as u64, 16) else {
+        return Vec::new();
+    };
+    let count = (le_u16(&dir, 12).unwrap_or(0) as usize + le_u16(&dir, 14).unwrap_or(0) as usize)
+        .min(MAX_RESOURCE_ENTRIES);
+    let Some(entries) = read_at(reader, section_offset + dir_offset as u64 + 16, count * 8) else {
+        return Vec::new();
+    };
+
+    entries
+        .chunks_exact(8)
+        .filter_map(|e| {
+            let name = le_u32(e, 0)?;
+            let target = le_u32(e, 4)?;
+            let name = if name & 0x8000_0000 != 0 {
+                let at = section_offset + (name & 0x7fff_ffff) as u64;
+                let len = le_u16(&read_at(reader, at, 2)?, 0)? as usize;
+                let raw = read_at(reader, at + 2, len * 2)?;
+                let units: Vec<u16> = raw
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                ResourceName::Name(String::from_utf16_lossy(&units))
+            } else {
+                ResourceName::Id(name)
+            };
+            Some((name, target))
+        })
+        .collect()
+}
+
+/// Lists the resources of one type (`RT_*`), walking the type/name/language levels.
+pub fn find_resources<R: Read + Seek>(
+    reader: &mut R,
+    header: &PeHeader,
+    resource_type: u32,
+) -> Vec<Resource> {
+    let Some(dir) = header.data_directory(IMAGE_DIRECTORY_ENTRY_RESOURCE) else {
+        return Vec::new();
+    };
+    let Some(section_offset) = header.rva_to_offset(dir.rva) else {
+        return Vec::new();
+    };
+
+    let mut resources = Vec::new();
+    let types = read_directory_entries(reader, section_offset, 0);
+    let Some((_, type_target)) = types
+        .into_iter()
+        .find(|(name, target)| *name == ResourceName::Id(resource_type) && target & 0x8000_0000 != 0)
+    else {
+        return resources;
+    };
+
+    for (name, name_target) in read_directory_entries(reader, section_offset, type_target & 0x7fff_ffff) {
+        if name_target & 0x8000_0000 == 0 {
+            continue;
+        }
+        for (language, lang_target) in
+            read_directory_entries(reader, section_offset, name_target & 0x7fff_ffff)
+        {
+            if lang_target & 0x8000_0000 != 0 {
+                continue;
+            }
+            let Some(entry) = read_at(reader, section_offset + lang_target as u64, 8) else {
+                continue;
+            };
+            let (Some(data_rva), Some(size)) = (le_u32(&entry, 0), le_u32(&entry, 4)) else {
+                continue;
+            };
+            let Some(offset) = header.rva_to_offset(data_rva) else {
+                continue;
+            };
+            let language = match language {
+                ResourceName::Id(id) => id,
+                ResourceName::Name(_) => 0,
+            };
+            resources.push(Resource {
+                name: name.clone(),
+                language,
+                offset,
+                size,
+            });
+        }
+    }
+    resources
+}
+
+pub fn read_resource<R: Read + Seek>(reader: &mut R, resource: &Resource) -> Option<Vec<u8>> {
+    if resource.size > MAX_RESOURCE_SIZE {
+        return None;
+    }
+    read_at(reader, resource.offset, resource.size as usize)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const SECTION_RVA: u32 = 0x1000;
+    const SECTION_OFFSET: usize = 0x200;
+    const OPTIONAL_HEADER: usize = 0x58;
+    const DATA_DIRECTORIES: usize = OPTIONAL_HEADER + 112;
+
+    fn put(buf: &mut [u8], at: usize, bytes: &[u8]) {
+        buf[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn put_directory(buf: &mut [u8], at: usize, entries: &[(u32, u32)]) {
+        put(buf, at + 14, &(entries.len() as u16).to_le_bytes());
+        for (i, (name, target)) in entries.iter().enumerate() {
+            put(buf, at + 16 + i * 8, &name.to_le_bytes());
+            put(buf, at + 20 + i * 8, &target.to_le_bytes());
+        }
+    }
+
+    /// An x64 image with one `.rsrc` section holding `resources`, each a type, an id
+    /// and its data, in language 1033.
+    pub(crate) fn image(characteristics: u16, subsystem: u16, resources: &[(u32, u32, &[u8])]) -> Vec<u8> {
+        let mut types: Vec<u32> = resources.iter().map(|r| r.0).collect();
+        types.sort();
+        types.dedup();
+        let directory_len = |entries: usize| 16 + entries * 8;
+        let tree_len = directory_len(types.len())
+            + types.len() * directory_len(0)
+            + resources.len() * (8 + directory_len(1));
+        let entries_at = tree_len;
+
+        let mut rsrc = vec![0u8; entries_at + resources.len() * 16];
+        let mut next = directory_len(types.len());
+        let mut root = Vec::new();
+        for &resource_type in &types {
+            let of_type: Vec<usize> = (0..resources.len()).filter(|&i| resources[i].0 == resource_type).collect();
+            let type_at = next;
+            next += directory_len(of_type.len());
+            let mut names = Vec::new();
+            for i in of_type {
+                put_directory(&mut rsrc, next, &[(1033, (entries_at + i * 16) as u32)]);
+                names.push((resources[i].1, next as u32 | 0x8000_0000));
+                next += directory_len(1);
+            }
+            put_directory(&mut rsrc, type_at, &names);
+            root.push((resource_type, type_at as u32 | 0x8000_0000));
+        }
+        put_directory(&mut rsrc, 0, &root);
+        for (i, (_, _, data)) in resources.iter().enumerate() {
+            rsrc.resize(rsrc.len().next_multiple_of(4), 0);
+            let rva = SECTION_RVA + rsrc.len() as u32;
+            put(&mut rsrc, entries_at + i * 16, &rva.to_le_bytes());
+            put(&mut rsrc, entries_at + i * 16 + 4, &(data.len() as u32).to_le_bytes());
+            rsrc.extend_from_slice(data);
+        }
+        let raw_size = rsrc.len().div_ceil(0x200) * 0x200;
+
+        let mut buf = vec![0u8; SECTION_OFFSET];
+        put(&mut buf, 0, b"MZ");
+        put(&mut buf, 0x3c, &0x40u32.to_le_bytes());
+        put(&mut buf, 0x40, b"PE\0\0");
+        put(&mut buf, 0x44, &0x8664u16.to_le_bytes());
+        put(&mut buf, 0x46, &1u16.to_le_bytes());
+        put(&mut buf, 0x54, &240u16.to_le_bytes());
+        put(&mut buf, 0x56, &characteristics.to_le_bytes());
+        put(&mut buf, OPTIONAL_HEADER, &0x20bu16.to_le_bytes());
+        put(&mut buf, OPTIONAL_HEADER + 68, &subsystem.to_le_bytes());
+        put(&mut buf, OPTIONAL_HEADER + 108, &16u32.to_le_bytes());
+        let resource_directory = DATA_DIRECTORIES + IMAGE_DIRECTORY_ENTRY_RESOURCE * 8;
+        put(&mut buf, resource_directory, &SECTION_RVA.to_le_bytes());
+        put(&mut buf, resource_directory + 4, &(rsrc.len() as u32).to_le_bytes());
+        let section = OPTIONAL_HEADER + 240;
+        put(&mut buf, section, b".rsrc");
+        put(&mut buf, section + 8, &(rsrc.len() as u32).to_le_bytes());
+        put(&mut buf, section + 12, &SECTION_RVA.to_le_bytes());
+        put(&mut buf, section + 16, &(raw_size as u32).to_le_bytes());
+        put(&mut buf, section + 20, &(SECTION_OFFSET as u32).to_le_bytes());
+        rsrc.resize(raw_size, 0);
+        buf.extend_from_slice(&rsrc);
+        buf
+    }
+
+    /// Points data directory `index` at `rva`, or at a file offset for the security one.
+    pub(crate) fn set_data_directory(image: &mut [u8], index: usize, rva: u32, size: u32) {
+        put(image, DATA_DIRECTORIES + index * 8, &rva.to_le_bytes());
+        put(image, DATA_DIRECTORIES + index * 8 + 4, &size.to_le_bytes());
+    }
+
+    #[test]
+    fn reads_header_and_resources() {
+        let data = image(0x0022, IMAGE_SUBSYSTEM_WINDOWS_GUI, &[
+            (RT_MANIFEST, 1, b"<assembly/>"),
+            (RT_ICON, 1, b"first"),
+            (RT_ICON, 2, b"second icon"),
+        ]);
+        let mut reader = Cursor::new(&data);
+        let header = read_header(&mut reader).unwrap();
+        assert_eq!(header.machine, 0x8664);
+        assert_eq!(header.subsystem, IMAGE_SUBSYSTEM_WINDOWS_GUI);
+        assert_eq!(header.sections.len(), 1);
+        assert_eq!(header.sections[0].name, ".rsrc");
+
+        let icons = find_resources(&mut reader, &header, RT_ICON);
+        assert_eq!(icons.iter().map(|r| r.name.clone()).collect::<Vec<_>>(), [ResourceName::Id(1), ResourceName::Id(2)]);
+        assert_eq!(icons[1].language, 1033);
+        assert_eq!(read_resource(&mut reader, &icons[1]).unwrap(), b"second icon");
+        assert!(find_resources(&mut reader, &header, RT_VERSION).is_empty());
+    }
+
+    #[test]
+    fn truncated_images_have_no_header() {
+        let data = image(0, IMAGE_SUBSYSTEM_WINDOWS_CUI, &[]);
+        assert!(read_header(&mut Cursor::new(&data[..0x100])).is_none());
+        assert!(read_header(&mut Cursor::new(&data[..0x20])).is_none());
+        let mut not_pe = data.clone();
+        not_pe[0x40] = b'N';
+        assert!(read_header(&mut Cursor::new(&not_pe)).is_none());
+    }
+}
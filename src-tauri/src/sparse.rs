@@ -0,0 +1,143 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// A file of which only some byte ranges are held in memory. Reads outside them fail
+/// and remember where they landed, so the caller can fetch that range and retry.
+pub struct SparseFile {
+    len: u64,
+    pos: u64,
+    /// Disjoint `(offset, bytes)` ranges.
+    ranges: Vec<(u64, Vec<u8>)>,
+    miss: Option<(u64, usize)>,
+}
+
+impl SparseFile {
+    pub fn new(len: u64) -> Self {
+        SparseFile {
+            len,
+            pos: 0,
+            ranges: Vec::new(),
+            miss: None,
+        }
+    }
+
+    /// Adds bytes starting at `offset`; only the part not held yet is kept.
+    pub fn insert(&mut self, offset: u64, data: Vec<u8>) {
+        let mut start = offset;
+        let end = offset + data.len() as u64;
+        while start < end {
+            match self.range_at(start) {
+                Some((range_offset, bytes)) => start = range_offset + bytes.len() as u64,
+                None => {
+                    let next = self
+                        .ranges
+                        .iter()
+                        .map(|(o, _)| *o)
+                        .filter(|&o| o > start)
+                        .min()
+                        .unwrap_or(end)
+                        .min(end);
+                    let slice = data[(start - offset) as usize..(next - offset) as usize].to_vec();
+                    self.ranges.push((start, slice));
+                    start = next;
+                }
+            }
+        }
+    }
+
+    /// Where the last failed read started and how many bytes it wanted, clearing it.
+    pub fn take_miss(&mut self) -> Option<(u64, usize)> {
+        self.miss.take()
+    }
+
+    fn range_at(&self, position: u64) -> Option<(u64, &[u8])> {
+        self.ranges
+            .iter()
+            .find(|(offset, bytes)| *offset <= position && position < offset + bytes.len() as u64)
+            .map(|(offset, bytes)| (*offset, bytes.as_slice()))
+    }
+}
+
+impl Read for SparseFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let Some((offset, bytes)) = self.range_at(self.pos) else {
+            // Keep the first miss: later ones may only be fallout from it
+            self.miss.get_or_insert((self.pos, buf.len()));
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Range not fetched"));
+        };
+        let available = &bytes[(self.pos - offset) as usize..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SparseFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek"))?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pe::{self, tests::image};
+
+    #[test]
+    fn reads_across_adjacent_ranges() {
+        let mut file = SparseFile::new(100);
+        file.insert(10, vec![1; 10]);
+        // Bytes already held are kept; only 20..25 is new
+        file.insert(15, vec![2; 10]);
+        file.insert(5, vec![3; 5]);
+
+        file.seek(SeekFrom::Start(5)).unwrap();
+        let mut buf = [0u8; 20];
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[..5], [3; 5]);
+        assert_eq!(buf[5..15], [1; 10]);
+        assert_eq!(buf[15..], [2; 5]);
+        assert_eq!(file.take_miss(), None);
+
+        assert_eq!(file.seek(SeekFrom::End(-1)).unwrap(), 99);
+        assert_eq!(file.seek(SeekFrom::Current(1)).unwrap(), 100);
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+        assert!(file.seek(SeekFrom::Current(-101)).is_err());
+    }
+
+    #[test]
+    fn misses_name_the_first_missing_range() {
+        let mut file = SparseFile::new(100);
+        file.insert(0, vec![7; 8]);
+        let mut buf = [0u8; 16];
+        assert!(file.read_exact(&mut buf).is_err());
+        file.seek(SeekFrom::Start(50)).unwrap();
+        assert!(file.read(&mut buf).is_err());
+        assert_eq!(file.take_miss(), Some((8, 8)));
+        assert_eq!(file.take_miss(), None);
+    }
+
+    #[test]
+    fn parsers_report_the_range_they_need() {
+        let data = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[]);
+        let mut file = SparseFile::new(data.len() as u64);
+        file.insert(0, data[..64].to_vec());
+        // The DOS header points past what is held
+        assert!(pe::read_header(&mut file).is_none());
+        assert_eq!(file.take_miss(), Some((0x40, 24)));
+
+        file.insert(0x40, data[0x40..0x200].to_vec());
+        assert!(pe::read_header(&mut file).is_some());
+        assert_eq!(file.take_miss(), None);
+    }
+}
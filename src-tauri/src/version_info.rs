@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+use crate::pe::{self, le_u16, le_u32, PeHeader};
+
+const VS_FIXEDFILEINFO_SIGNATURE: u32 = 0xfeef_04bd;
+/// en-US, Unicode: the table Explorer shows when it is present.
+const PREFERRED_TABLE: &str = "040904b0";
+
+/// Strings and fixed version of a `VS_VERSIONINFO` resource.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub company_name: Option<String>,
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+    pub legal_copyright: Option<String>,
+    /// `ProductVersion` string, normalized to dotted form; falls back to the fixed version.
+    pub product_version: Option<String>,
+    pub file_version: Option<String>,
+    /// Name the file was built as, before any renaming for distribution.
+    #[serde(default)]
+    pub original_filename: Option<String>,
+}
+
+/// One node of the version resource tree.
+struct Block<'a> {
+    key: String,
+    value: &'a [u8],
+    is_text: bool,
+    children: Vec<Block<'a>>,
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn utf16_until_nul(data: &[u8]) -> (String, usize) {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    let consumed = (units.len() + 1) * 2;
+    (String::from_utf16_lossy(&units), consumed)
+}
+
+/// Parses the block starting at `offset`; offsets stay relative to the resource start
+/// because alignment is defined against it.
+fn parse_block(data: &[u8], offset: usize, depth: usize) -> Option<Block<'_>> {
+    if depth > 8 {
+        return None;
+    }
+    let length = le_u16(data, offset)? as usize;
+    let value_length = le_u16(data, offset + 2)? as usize;
+    let is_text = le_u16(data, offset + 4)? == 1;
+    let end = (offset + length).min(data.len());
+    if length < 6 || offset + 6 > end {
+        return None;
+    }
+
+    let (key, consumed) = utf16_until_nul(&data[offset + 6..end]);
+    let value_start = align4(offset + 6 + consumed);
+    // Text values count UTF-16 units, binary ones count bytes
+    let value_size = if is_text { value_length * 2 } else { value_length };
+    let value_end = (value_start + value_size).min(end);
+    let value = data.get(value_start.min(value_end)..value_end).unwrap_or(&[]);
+
+    let mut children = Vec::new();
+    let mut child = align4(value_end);
+    while child + 6 <= end {
+        let Some(block) = parse_block(&data[..end], child, depth + 1) else {
+            break;
+        };
+        let child_length = le_u16(data, child)? as usize;
+        children.push(block);
+        child = align4(child + child_length);
+    }
+
+    Some(Block {
+        key,
+        value,
+        is_text,
+        children,
+    })
+}
+
+fn text_value(block: &Block) -> Option<String> {
+    let text = if block.is_text {
+        utf16_until_nul(block.value).0
+    } else {
+        String::from_utf8_lossy(block.value).trim_end_matches('\0').to_string()
+    };
+    let text = text.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Turns `1, 2, 3, 0` into `1.2.3.0`; anything else is returned trimmed.
+fn normalize_version(version: &str) -> String {
+    let version = version.trim();
+    if version.contains(',') {
+        version
+            .split(',')
+            .map(|part| part.trim())
+            .collect::<Vec<_>>()
+            .join(".")
+    } else {
+        version.to_string()
+    }
+}
+
+fn fixed_versions(value: &[u8]) -> Option<(String, String)> {
+    if le_u32(value, 0)? != VS_FIXEDFILEINFO_SIGNATURE {
+        return None;
+    }
+    let format = |ms: u32, ls: u32| {
+        format!("{}.{}.{}.{}", ms >> 16, ms & 0xffff, ls >> 16, ls & 0xffff)
+    };
+    Some((
+        format(le_u32(value, 8)?, le_u32(value, 12)?),
+        format(le_u32(value, 16)?, le_u32(value, 20)?),
+    ))
+}
+
+/// Parses the raw bytes of an `RT_VERSION` resource.
+pub fn parse(data: &[u8]) -> Option<VersionInfo> {
+    let root = parse_block(data, 0, 0)?;
+    if root.key != "VS_VERSION_INFO" {
+        return None;
+    }
+    let fixed = fixed_versions(root.value);
+
+    let tables: Vec<&Block> = root
+        .children
+        .iter()
+        .filter(|b| b.key == "StringFileInfo")
+        .flat_map(|b| b.children.iter())
+        .collect();
+    let table = tables
+        .iter()
+        .find(|t| t.key.eq_ignore_ascii_case(PREFERRED_TABLE))
+        .or_else(|| tables.iter().find(|t| t.key.to_ascii_lowercase().starts_with("0409")))
+        .or_else(|| tables.first());
+    let string = |name: &str| {
+        table.and_then(|t| {
+            t.children
+                .iter()
+                .find(|s| s.key == name)
+                .and_then(text_value)
+        })
+    };
+
+    let info = VersionInfo {
+        company_name: string("CompanyName"),
+        product_name: string("ProductName"),
+        file_description: string("FileDescription"),
+        legal_copyright: string("LegalCopyright"),
+        product_version: string("ProductVersion")
+            .map(|v| normalize_version(&v))
+            .or_else(|| fixed.as_ref().map(|(_, product)| product.clone())),
+        file_version: string("FileVersion")
+            .map(|v| normalize_version(&v))
+            .or_else(|| fixed.as_ref().map(|(file, _)| file.clone())),
+        original_filename: string("OriginalFilename"),
+    };
+    Some(info)
+}
+
+/// Reads the version resource of a PE image, preferring the en-US one.
+pub fn read<R: Read + Seek>(reader: &mut R, header: &PeHeader) -> Option<VersionInfo> {
+    let resources = pe::find_resources(reader, header, pe::RT_VERSION);
+    let resource = resources
+        .iter()
+        .find(|r| r.language == 0x0409)
+        .or_else(|| resources.first())?;
+    parse(&pe::read_resource(reader, resource)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pe::tests::image;
+    use std::io::Cursor;
+
+    fn utf16z(text: &str) -> Vec<u8> {
+        text.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn pad4(bytes: &mut Vec<u8>) {
+        bytes.resize(align4(bytes.len()), 0);
+    }
+
+    /// One version block; text values are counted in UTF-16 units, binary ones in bytes.
+    fn block(key: &str, value: &[u8], is_text: bool, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = vec![0; 6];
+        out.extend(utf16z(key));
+        pad4(&mut out);
+        out.extend(value);
+        for child in children {
+            pad4(&mut out);
+            out.extend(child);
+        }
+        let value_length = if is_text { value.len() / 2 } else { value.len() };
+        let length = out.len() as u16;
+        out[..2].copy_from_slice(&length.to_le_bytes());
+        out[2..4].copy_from_slice(&(value_length as u16).to_le_bytes());
+        out[4..6].copy_from_slice(&u16::from(is_text).to_le_bytes());
+        out
+    }
+
+    fn strings(language: &str, values: &[(&str, &str)]) -> Vec<u8> {
+        let children: Vec<Vec<u8>> = values.iter().map(|(k, v)| block(k, &utf16z(v), true, &[])).collect();
+        block(language, &[], true, &children)
+    }
+
+    fn version_resource(tables: &[Vec<u8>]) -> Vec<u8> {
+        let mut fixed = VS_FIXEDFILEINFO_SIGNATURE.to_le_bytes().to_vec();
+        fixed.extend(0x0001_0000u32.to_le_bytes());
+        for part in [(2 << 16) | 5, (7 << 16) | 11, (2 << 16) | 6, 0u32] {
+            fixed.extend(part.to_le_bytes());
+        }
+        fixed.resize(52, 0);
+        block("VS_VERSION_INFO", &fixed, false, &[block("StringFileInfo", &[], true, tables)])
+    }
+
+    #[test]
+    fn prefers_the_english_string_table() {
+        let data = version_resource(&[
+            strings("040704b0", &[("CompanyName", "Contoso GmbH"), ("ProductName", "Betrachter")]),
+            strings("040904b0", &[
+                ("CompanyName", "Contoso Ltd."),
+                ("ProductName", "Viewer"),
+                ("FileDescription", "Contoso Viewer"),
+                ("LegalCopyright", "  "),
+                ("ProductVersion", "2, 6, 0, 0"),
+                ("OriginalFilename", "viewer.exe"),
+            ]),
+        ]);
+        let info = parse(&data).unwrap();
+        assert_eq!(info.company_name.as_deref(), Some("Contoso Ltd."));
+        assert_eq!(info.product_name.as_deref(), Some("Viewer"));
+        assert_eq!(info.file_description.as_deref(), Some("Contoso Viewer"));
+        assert_eq!(info.legal_copyright, None);
+        assert_eq!(info.product_version.as_deref(), Some("2.6.0.0"));
+        // No FileVersion string: the fixed one is used
+        assert_eq!(info.file_version.as_deref(), Some("2.5.7.11"));
+        assert_eq!(info.original_filename.as_deref(), Some("viewer.exe"));
+
+        let german = parse(&version_resource(&[strings("040704b0", &[("ProductName", "Betrachter")])])).unwrap();
+        assert_eq!(german.product_name.as_deref(), Some("Betrachter"));
+        assert_eq!(german.product_version.as_deref(), Some("2.6.0.0"));
+    }
+
+    #[test]
+    fn reads_the_resource_of_an_image() {
+        let data = version_resource(&[strings("040904b0", &[("ProductName", "Viewer")])]);
+        let pe = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[(pe::RT_VERSION, 1, &data)]);
+        let mut reader = Cursor::new(&pe);
+        let header = pe::read_header(&mut reader).unwrap();
+        assert_eq!(read(&mut reader, &header).unwrap().product_name.as_deref(), Some("Viewer"));
+
+        let bare = image(0x0022, pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, &[]);
+        let mut reader = Cursor::new(&bare);
+        let header = pe::read_header(&mut reader).unwrap();
+        assert!(read(&mut reader, &header).is_none());
+    }
+
+    #[test]
+    fn damaged_resources_are_not_read() {
+        let data = version_resource(&[strings("040904b0", &[("ProductName", "Viewer")])]);
+        assert!(parse(&data[..4]).is_none());
+        assert!(parse(&[]).is_none());
+        assert!(parse(&block("VS_VERSION_INFX", &[], false, &[])).is_none());
+        // Cut anywhere, the resource never reads past its end
+        for len in 0..data.len() {
+            let _ = parse(&data[..len]);
+        }
+        let mut lying = data.clone();
+        lying[..2].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert_eq!(parse(&lying).unwrap().product_name.as_deref(), Some("Viewer"));
+    }
+}
@@ -1,6 +1,40 @@
 use serde::{Deserialize, Serialize};
 
+use crate::associations::{normalize_file_extension, normalize_protocol};
+
 const MANIFEST_SCHEMA_VERSION: &str = "1.9.0";
+/// `ReturnResponse` values accepted by the installer schema.
+const RETURN_RESPONSES: &[&str] = &[
+    "packageInUse",
+    "packageInUseByApplication",
+    "installInProgress",
+    "fileInUse",
+    "missingDependency",
+    "diskFull",
+    "insufficientMemory",
+    "invalidParameter",
+    "noNetwork",
+    "contactSupport",
+    "rebootRequiredToFinish",
+    "rebootRequiredForInstall",
+    "rebootInitiated",
+    "cancelledByUser",
+    "alreadyInstalled",
+    "downgrade",
+    "blockedByPolicy",
+    "systemNotSupported",
+    "custom",
+];
+/// Return codes are Win32 exit codes, read as either `i32` or `u32`.
+const RETURN_CODE_RANGE: std::ops::RangeInclusive<i64> = -2_147_483_648..=4_294_967_295;
+const MAX_SUCCESS_CODES: usize = 16;
+const MAX_EXPECTED_RETURN_CODES: usize = 128;
+const MAX_RETURN_RESPONSE_URL_LENGTH: usize = 2048;
+const MAX_COMMANDS: usize = 16;
+const MAX_COMMAND_LENGTH: usize = 40;
+const MAX_PROTOCOLS: usize = 64;
+const MAX_PROTOCOL_LENGTH: usize = 2048;
+const MAX_FILE_EXTENSIONS: usize = 512;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -9,12 +43,14 @@ pub struct ManifestData {
     pub package_version: String,
     pub default_locale: String,
     pub minimum_os_version: Option<String>,
+    /// Root-level `Dependencies`, shared by every installer that does not set its own.
+    pub dependencies: Option<Dependencies>,
     pub installers: Vec<InstallerEntry>,
     pub locale: LocaleData,
     pub additional_locales: Option<Vec<LocaleData>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct InstallerEntry {
     pub architecture: String,
@@ -23,14 +59,86 @@ pub struct InstallerEntry {
     pub installer_sha256: String,
     pub scope: Option<String>,
     pub installer_switches: Option<InstallerSwitches>,
+    /// Exit codes besides 0 that mean the install succeeded.
+    pub installer_success_codes: Option<Vec<i64>>,
+    pub expected_return_codes: Option<Vec<ExpectedReturnCode>>,
     pub install_modes: Option<Vec<String>>,
     pub signature_sha256: Option<String>,
     pub product_code: Option<String>,
+    pub package_family_name: Option<String>,
     pub upgrade_behavior: Option<String>,
     pub elevation_requirement: Option<String>,
+    pub apps_and_features_entries: Option<Vec<AppsAndFeaturesEntry>>,
+    /// Commands the package puts on the PATH; a portable is linked under the first one.
+    pub commands: Option<Vec<String>>,
+    /// URL schemes the package registers, e.g. `vscode`.
+    pub protocols: Option<Vec<String>>,
+    /// File extensions the package associates with itself, lowercase and without the dot.
+    pub file_extensions: Option<Vec<String>>,
+    pub dependencies: Option<Dependencies>,
+    pub installation_metadata: Option<InstallationMetadata>,
+    /// Only emitted for `zip` installers.
+    pub nested_installer_type: Option<String>,
+    pub nested_installer_files: Option<Vec<NestedInstallerFile>>,
+    pub archive_binaries_depend_on_path: Option<bool>,
+    /// Detected installer framework; only used to pick default switches, never emitted.
+    pub installer_technology: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsAndFeaturesEntry {
+    pub display_name: Option<String>,
+    pub publisher: Option<String>,
+    pub display_version: Option<String>,
+    pub product_code: Option<String>,
+    pub upgrade_code: Option<String>,
+    /// Only needed when the entry was registered by a different technology than the
+    /// installer's, e.g. an MSI chained by a Burn bundle.
+    pub installer_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedReturnCode {
+    pub installer_return_code: i64,
+    /// One of the schema's `ReturnResponse` values, e.g. `rebootRequiredToFinish`.
+    pub return_response: String,
+    pub return_response_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallationMetadata {
+    /// Where the installer puts the package by default, e.g. `%ProgramFiles%\\App`.
+    pub default_install_location: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependencies {
+    pub windows_features: Option<Vec<String>>,
+    pub windows_libraries: Option<Vec<String>>,
+    pub package_dependencies: Option<Vec<PackageDependency>>,
+    /// Free-form requirements winget cannot install, e.g. `Java Runtime Environment 8`.
+    pub external_dependencies: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageDependency {
+    pub package_identifier: String,
+    pub minimum_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NestedInstallerFile {
+    pub relative_file_path: String,
+    pub portable_command_alias: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct InstallerSwitches {
     pub silent: Option<String>,
@@ -64,6 +172,18 @@ pub struct LocaleData {
     pub tags: Option<Vec<String>>,
     pub release_notes: Option<String>,
     pub release_notes_url: Option<String>,
+    pub icons: Option<Vec<Icon>>,
+}
+
+/// A hosted icon listed under `Icons`; icons without a URL are left out.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Icon {
+    pub icon_url: String,
+    pub icon_file_type: String,
+    pub icon_resolution: Option<String>,
+    pub icon_theme: Option<String>,
+    pub icon_sha256: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -110,6 +230,7 @@ fn needs_yaml_quotes(value: &str) -> bool {
             | '|'
             | '>'
             | '@'
+            | '%'
             | '`'
             | '"'
             | '\'')
@@ -147,6 +268,282 @@ fn format_yaml_field(key: &str, value: &str) -> String {
     }
 }
 
+/// Default `(Silent, SilentWithProgress)` switches for `exe` installers whose framework is
+/// known. Any other `exe` needs the user's own, since winget-pkgs rejects one without them.
+///
+/// Typed installers (inno, nullsoft, burn, wix, msi) are left alone since winget
+/// already knows their switches.
+fn default_exe_switches(technology: Option<&str>) -> Option<(&'static str, &'static str)> {
+    match technology? {
+        "installShield" => Some(("/s /v\"/qn\"", "/s /v\"/qb\"")),
+        "squirrel" => Some(("--silent", "--silent")),
+        "advancedInstaller" => Some((
+            "/exenoui /quiet /norestart",
+            "/exenoui /passive /norestart",
+        )),
+        "nullsoft" => Some(("/S", "/S")),
+        "inno" => Some((
+            "/VERYSILENT /SUPPRESSMSGBOXES /NORESTART /SP-",
+            "/SILENT /SUPPRESSMSGBOXES /NORESTART /SP-",
+        )),
+        "burn" => Some(("/quiet /norestart", "/passive /norestart")),
+        _ => None,
+    }
+}
+
+/// Whether the installer, or the installer nested in its archive, is a plain `exe`.
+fn runs_exe(inst: &InstallerEntry) -> bool {
+    inst.installer_type.eq_ignore_ascii_case("exe")
+        || (inst.installer_type.eq_ignore_ascii_case("zip")
+            && inst.nested_installer_type.as_deref() == Some("exe"))
+}
+
+fn apps_and_features_yaml(entries: &[AppsAndFeaturesEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let fields = [
+            ("DisplayName", &entry.display_name),
+            ("Publisher", &entry.publisher),
+            ("DisplayVersion", &entry.display_version),
+            ("ProductCode", &entry.product_code),
+            ("UpgradeCode", &entry.upgrade_code),
+            ("InstallerType", &entry.installer_type),
+        ];
+        let mut first = true;
+        for (key, value) in fields {
+            let Some(v) = value.as_ref().filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            out.push_str(if first { "  - " } else { "    " });
+            out.push_str(&format!("{}: {}\n", key, format_yaml_scalar(v)));
+            first = false;
+        }
+    }
+    if out.is_empty() {
+        out
+    } else {
+        format!("  AppsAndFeaturesEntries:\n{}", out)
+    }
+}
+
+/// `Dependencies` block; `indent` is `""` at the root and `"  "` inside an installer.
+fn dependencies_yaml(dependencies: &Dependencies, indent: &str) -> String {
+    let mut body = String::new();
+    let lists = [
+        ("WindowsFeatures", &dependencies.windows_features),
+        ("WindowsLibraries", &dependencies.windows_libraries),
+    ];
+    for (key, values) in lists {
+        body.push_str(&string_list_yaml(key, values, indent));
+    }
+    let packages: Vec<&PackageDependency> = dependencies
+        .package_dependencies
+        .iter()
+        .flatten()
+        .filter(|p| !p.package_identifier.is_empty())
+        .collect();
+    if !packages.is_empty() {
+        body.push_str(&format!("{}  PackageDependencies:\n", indent));
+        for package in packages {
+            body.push_str(&format!(
+                "{}  - PackageIdentifier: {}\n",
+                indent,
+                format_yaml_scalar(&package.package_identifier)
+            ));
+            if let Some(version) = package.minimum_version.as_ref().filter(|v| !v.is_empty()) {
+                body.push_str(&format!(
+                    "{}    MinimumVersion: {}\n",
+                    indent,
+                    format_yaml_scalar(version)
+                ));
+            }
+        }
+    }
+    body.push_str(&string_list_yaml(
+        "ExternalDependencies",
+        &dependencies.external_dependencies,
+        indent,
+    ));
+    if body.is_empty() {
+        body
+    } else {
+        format!("{}Dependencies:\n{}", indent, body)
+    }
+}
+
+fn string_list_yaml(key: &str, values: &Option<Vec<String>>, indent: &str) -> String {
+    let values: Vec<&String> = values.iter().flatten().filter(|v| !v.is_empty()).collect();
+    if values.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("{}  {}:\n", indent, key);
+    for value in values {
+        out.push_str(&format!("{}  - {}\n", indent, format_yaml_scalar(value)));
+    }
+    out
+}
+
+fn return_codes_yaml(inst: &InstallerEntry) -> String {
+    let mut out = String::new();
+    if let Some(codes) = inst.installer_success_codes.as_ref().filter(|c| !c.is_empty()) {
+        out.push_str("  InstallerSuccessCodes:\n");
+        for code in codes {
+            out.push_str(&format!("  - {}\n", code));
+        }
+    }
+    if let Some(codes) = inst.expected_return_codes.as_ref().filter(|c| !c.is_empty()) {
+        out.push_str("  ExpectedReturnCodes:\n");
+        for code in codes {
+            out.push_str(&format!("  - InstallerReturnCode: {}\n", code.installer_return_code));
+            out.push_str(&format!("    ReturnResponse: {}\n", code.return_response));
+            if let Some(url) = code.return_response_url.as_ref().filter(|u| !u.is_empty()) {
+                out.push_str(&format!("    ReturnResponseUrl: {}\n", format_yaml_scalar(url)));
+            }
+        }
+    }
+    out
+}
+
+/// Checks an installer's lists against the schema limits, naming the installer at fault.
+fn validate_installer(index: usize, inst: &InstallerEntry) -> Result<(), String> {
+    let label = format!("Installer {} ({})", index + 1, inst.architecture);
+    validate_return_codes(&label, inst)?;
+
+    if runs_exe(inst) && default_exe_switches(inst.installer_technology.as_deref()).is_none() {
+        let switches = inst.installer_switches.as_ref();
+        let set = |value: Option<&String>| value.is_some_and(|v| !v.is_empty());
+        if !switches.is_some_and(|s| set(s.silent.as_ref()) && set(s.silent_with_progress.as_ref())) {
+            return Err(format!(
+                "{}: enter the Silent and SilentWithProgress switches of this exe installer",
+                label
+            ));
+        }
+    }
+
+    let commands = inst.commands.as_deref().unwrap_or_default();
+    let command_rule = format!("1 to {} characters", MAX_COMMAND_LENGTH);
+    validate_list(&label, "Commands", &command_rule, commands, MAX_COMMANDS, |command| {
+        (1..=MAX_COMMAND_LENGTH).contains(&command.chars().count())
+    })?;
+    let protocols = inst.protocols.as_deref().unwrap_or_default();
+    let protocol_rule = "a lowercase URL scheme";
+    validate_list(&label, "Protocols", protocol_rule, protocols, MAX_PROTOCOLS, |protocol| {
+        protocol.len() <= MAX_PROTOCOL_LENGTH
+            && normalize_protocol(protocol).as_deref() == Some(protocol)
+    })?;
+    let extensions = inst.file_extensions.as_deref().unwrap_or_default();
+    let extension_rule = "lowercase, without the dot";
+    validate_list(&label, "FileExtensions", extension_rule, extensions, MAX_FILE_EXTENSIONS, |extension| {
+        normalize_file_extension(extension).as_deref() == Some(extension)
+    })
+}
+
+fn validate_list(
+    label: &str,
+    key: &str,
+    rule: &str,
+    values: &[String],
+    max_items: usize,
+    is_valid: impl Fn(&str) -> bool,
+) -> Result<(), String> {
+    if values.len() > max_items {
+        return Err(format!("{}: at most {} {} are allowed", label, max_items, key));
+    }
+    for (i, value) in values.iter().enumerate() {
+        if !is_valid(value) {
+            return Err(format!("{}: '{}' in {} must be {}", label, value, key, rule));
+        }
+        if values[..i].contains(value) {
+            return Err(format!("{}: {} lists '{}' twice", label, key, value));
+        }
+    }
+    Ok(())
+}
+
+fn validate_return_codes(label: &str, inst: &InstallerEntry) -> Result<(), String> {
+    let check_code = |code: i64| {
+        if code == 0 || !RETURN_CODE_RANGE.contains(&code) {
+            Err(format!("{}: {} is not a valid installer return code", label, code))
+        } else {
+            Ok(())
+        }
+    };
+
+    let success = inst.installer_success_codes.as_deref().unwrap_or_default();
+    if success.len() > MAX_SUCCESS_CODES {
+        return Err(format!("{}: at most {} InstallerSuccessCodes are allowed", label, MAX_SUCCESS_CODES));
+    }
+    for (i, &code) in success.iter().enumerate() {
+        check_code(code)?;
+        if success[..i].contains(&code) {
+            return Err(format!("{}: InstallerSuccessCodes lists {} twice", label, code));
+        }
+    }
+
+    let expected = inst.expected_return_codes.as_deref().unwrap_or_default();
+    if expected.len() > MAX_EXPECTED_RETURN_CODES {
+        return Err(format!("{}: at most {} ExpectedReturnCodes are allowed", label, MAX_EXPECTED_RETURN_CODES));
+    }
+    for (i, entry) in expected.iter().enumerate() {
+        let code = entry.installer_return_code;
+        check_code(code)?;
+        if expected[..i].iter().any(|e| e.installer_return_code == code) {
+            return Err(format!("{}: ExpectedReturnCodes lists {} twice", label, code));
+        }
+        if success.contains(&code) {
+            return Err(format!("{}: {} is both a success code and an expected return code", label, code));
+        }
+        if !RETURN_RESPONSES.contains(&entry.return_response.as_str()) {
+            return Err(format!(
+                "{}: '{}' is not a valid ReturnResponse for code {}",
+                label, entry.return_response, code
+            ));
+        }
+        if let Some(url) = entry.return_response_url.as_deref().filter(|u| !u.is_empty()) {
+            let scheme = url.split_once("://").map(|(scheme, rest)| (scheme.to_ascii_lowercase(), rest));
+            let is_http = scheme.is_some_and(|(scheme, rest)| (scheme == "http" || scheme == "https") && !rest.is_empty());
+            if !is_http || url.len() > MAX_RETURN_RESPONSE_URL_LENGTH {
+                return Err(format!("{}: the ReturnResponseUrl of code {} must be an http(s) URL", label, code));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn nested_installer_yaml(inst: &InstallerEntry) -> String {
+    let mut out = String::new();
+    if let Some(ref nested) = inst.nested_installer_type {
+        if !nested.is_empty() {
+            out.push_str(&format!(
+                "  NestedInstallerType: {}\n",
+                format_yaml_scalar(nested)
+            ));
+        }
+    }
+    let files: Vec<&NestedInstallerFile> = inst
+        .nested_installer_files
+        .iter()
+        .flatten()
+        .filter(|f| !f.relative_file_path.is_empty())
+        .collect();
+    if !files.is_empty() {
+        out.push_str("  NestedInstallerFiles:\n");
+        for file in files {
+            out.push_str(&format!(
+                "  - RelativeFilePath: {}\n",
+                format_yaml_scalar(&file.relative_file_path)
+            ));
+            if let Some(alias) = file.portable_command_alias.as_ref().filter(|a| !a.is_empty()) {
+                out.push_str(&format!(
+                    "    PortableCommandAlias: {}\n",
+                    format_yaml_scalar(alias)
+                ));
+            }
+        }
+    }
+    out
+}
+
 fn schema_header(manifest_kind: &str) -> String {
     format!(
         "# yaml-language-server: $schema=https://aka.ms/winget-manifest.{}.{}.schema.json\n\n",
@@ -177,6 +574,9 @@ fn generate_installer_yaml(m: &ManifestData) -> YamlFile {
             content.push_str(&format_yaml_field("MinimumOSVersion", os));
         }
     }
+    if let Some(ref dependencies) = m.dependencies {
+        content.push_str(&dependencies_yaml(dependencies, ""));
+    }
 
     content.push_str("Installers:\n");
 
@@ -189,6 +589,10 @@ fn generate_installer_yaml(m: &ManifestData) -> YamlFile {
             "  InstallerType: {}\n",
             format_yaml_scalar(&inst.installer_type)
         ));
+        let is_archive = inst.installer_type.eq_ignore_ascii_case("zip");
+        if is_archive {
+            content.push_str(&nested_installer_yaml(inst));
+        }
         content.push_str(&format!(
             "  InstallerUrl: {}\n",
             format_yaml_scalar(&inst.installer_url)
@@ -210,6 +614,14 @@ fn generate_installer_yaml(m: &ManifestData) -> YamlFile {
                 content.push_str(&format!("  ProductCode: {}\n", format_yaml_scalar(pc)));
             }
         }
+        if let Some(ref pfn) = inst.package_family_name {
+            if !pfn.is_empty() {
+                content.push_str(&format!(
+                    "  PackageFamilyName: {}\n",
+                    format_yaml_scalar(pfn)
+                ));
+            }
+        }
         if let Some(ref ub) = inst.upgrade_behavior {
             if !ub.is_empty() {
                 content.push_str(&format!(
@@ -234,21 +646,26 @@ fn generate_installer_yaml(m: &ManifestData) -> YamlFile {
                 }
             }
         }
-        let is_exe = inst.installer_type.eq_ignore_ascii_case("exe");
+        if is_archive && inst.archive_binaries_depend_on_path == Some(true) {
+            content.push_str("  ArchiveBinariesDependOnPath: true\n");
+        }
+        let defaults = runs_exe(inst)
+            .then(|| default_exe_switches(inst.installer_technology.as_deref()))
+            .flatten();
         let silent = inst
             .installer_switches
             .as_ref()
             .and_then(|s| s.silent.as_ref())
             .filter(|v| !v.is_empty())
             .cloned()
-            .or_else(|| if is_exe { Some("/S".to_string()) } else { None });
+            .or_else(|| defaults.map(|(v, _)| v.to_string()));
         let silent_with_progress = inst
             .installer_switches
             .as_ref()
             .and_then(|s| s.silent_with_progress.as_ref())
             .filter(|v| !v.is_empty())
             .cloned()
-            .or_else(|| if is_exe { Some("/S".to_string()) } else { None });
+            .or_else(|| defaults.map(|(_, v)| v.to_string()));
         let custom = inst
             .installer_switches
             .as_ref()
@@ -295,6 +712,33 @@ fn generate_installer_yaml(m: &ManifestData) -> YamlFile {
                 content.push_str(&format!("    Repair: {}\n", format_yaml_scalar(&v)));
             }
         }
+        content.push_str(&return_codes_yaml(inst));
+        let lists = [
+            ("Commands", &inst.commands),
+            ("Protocols", &inst.protocols),
+            ("FileExtensions", &inst.file_extensions),
+        ];
+        for (key, values) in lists {
+            content.push_str(&string_list_yaml(key, values, ""));
+        }
+        if let Some(ref dependencies) = inst.dependencies {
+            content.push_str(&dependencies_yaml(dependencies, "  "));
+        }
+        if let Some(location) = inst
+            .installation_metadata
+            .as_ref()
+            .and_then(|m| m.default_install_location.as_ref())
+            .filter(|l| !l.is_empty())
+        {
+            content.push_str("  InstallationMetadata:\n");
+            content.push_str(&format!(
+                "    DefaultInstallLocation: {}\n",
+                format_yaml_scalar(location)
+            ));
+        }
+        if let Some(ref entries) = inst.apps_and_features_entries {
+            content.push_str(&apps_and_features_yaml(entries));
+        }
     }
 
     content.push_str(&format_yaml_field("ManifestType", "installer"));
@@ -306,6 +750,33 @@ fn generate_installer_yaml(m: &ManifestData) -> YamlFile {
     }
 }
 
+fn icons_yaml(icons: &Option<Vec<Icon>>) -> String {
+    let icons: Vec<&Icon> = icons
+        .iter()
+        .flatten()
+        .filter(|i| !i.icon_url.is_empty() && !i.icon_file_type.is_empty())
+        .collect();
+    if icons.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("Icons:\n");
+    for icon in icons {
+        out.push_str(&format!("- IconUrl: {}\n", format_yaml_scalar(&icon.icon_url)));
+        out.push_str(&format!("  IconFileType: {}\n", format_yaml_scalar(&icon.icon_file_type)));
+        let fields = [
+            ("IconResolution", &icon.icon_resolution),
+            ("IconTheme", &icon.icon_theme),
+            ("IconSha256", &icon.icon_sha256),
+        ];
+        for (key, value) in fields {
+            if let Some(v) = value.as_ref().filter(|v| !v.is_empty()) {
+                out.push_str(&format!("  {}: {}\n", key, format_yaml_scalar(v)));
+            }
+        }
+    }
+    out
+}
+
 fn generate_locale_yaml(m: &ManifestData) -> YamlFile {
     let l = &m.locale;
     let mut content = schema_header("defaultLocale");
@@ -339,6 +810,7 @@ fn generate_locale_yaml(m: &ManifestData) -> YamlFile {
 
     content.push_str(&opt_field("ReleaseNotes", &l.release_notes));
     content.push_str(&opt_field("ReleaseNotesUrl", &l.release_notes_url));
+    content.push_str(&icons_yaml(&l.icons));
     content.push_str(&format_yaml_field("ManifestType", "defaultLocale"));
     content.push_str(&format_yaml_field("ManifestVersion", MANIFEST_SCHEMA_VERSION));
 
@@ -382,6 +854,7 @@ fn generate_additional_locale_yaml(m: &ManifestData, l: &LocaleData) -> YamlFile
 
     content.push_str(&opt_field("ReleaseNotes", &l.release_notes));
     content.push_str(&opt_field("ReleaseNotesUrl", &l.release_notes_url));
+    content.push_str(&icons_yaml(&l.icons));
     content.push_str(&format_yaml_field("ManifestType", "locale"));
     content.push_str(&format_yaml_field("ManifestVersion", MANIFEST_SCHEMA_VERSION));
 
@@ -394,7 +867,11 @@ fn generate_additional_locale_yaml(m: &ManifestData, l: &LocaleData) -> YamlFile
     }
 }
 
-pub fn generate_yaml(manifest: &ManifestData) -> Vec<YamlFile> {
+pub fn generate_yaml(manifest: &ManifestData) -> Result<Vec<YamlFile>, String> {
+    for (index, inst) in manifest.installers.iter().enumerate() {
+        validate_installer(index, inst)?;
+    }
+
     let mut files = vec![
         generate_version_yaml(manifest),
         generate_installer_yaml(manifest),
@@ -407,5 +884,272 @@ pub fn generate_yaml(manifest: &ManifestData) -> Vec<YamlFile> {
         }
     }
 
-    files
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installer() -> InstallerEntry {
+        InstallerEntry {
+            architecture: "x64".to_string(),
+            installer_type: "msi".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    #[test]
+    fn list_validation_names_the_failure() {
+        let always = |_: &str| true;
+        let values = strings(&["a", "b"]).unwrap();
+        assert!(validate_list("Installer 1 (x64)", "Commands", "rule", &values, 2, always).is_ok());
+        assert_eq!(
+            validate_list("Installer 1 (x64)", "Commands", "rule", &values, 1, always).unwrap_err(),
+            "Installer 1 (x64): at most 1 Commands are allowed"
+        );
+        assert_eq!(
+            validate_list("Installer 1 (x64)", "Commands", "short", &values, 2, |v| v != "b").unwrap_err(),
+            "Installer 1 (x64): 'b' in Commands must be short"
+        );
+        let twice = strings(&["a", "a"]).unwrap();
+        assert_eq!(
+            validate_list("Installer 1 (x64)", "Commands", "rule", &twice, 2, always).unwrap_err(),
+            "Installer 1 (x64): Commands lists 'a' twice"
+        );
+    }
+
+    #[test]
+    fn commands_are_limited_in_count_and_length() {
+        let mut inst = installer();
+        inst.commands = Some(vec!["x".repeat(MAX_COMMAND_LENGTH)]);
+        assert!(validate_installer(0, &inst).is_ok());
+        inst.commands = Some(vec!["x".repeat(MAX_COMMAND_LENGTH + 1)]);
+        assert!(validate_installer(0, &inst).is_err());
+        inst.commands = strings(&[""]);
+        assert!(validate_installer(0, &inst).is_err());
+        inst.commands = Some((0..=MAX_COMMANDS).map(|i| format!("tool{}", i)).collect());
+        assert_eq!(
+            validate_installer(1, &inst).unwrap_err(),
+            "Installer 2 (x64): at most 16 Commands are allowed"
+        );
+    }
+
+    #[test]
+    fn protocols_and_extensions_must_be_normalized() {
+        let mut inst = installer();
+        inst.protocols = strings(&["vscode", "vscode-insiders"]);
+        inst.file_extensions = strings(&["txt", "tar.gz"]);
+        assert!(validate_installer(0, &inst).is_ok());
+
+        inst.protocols = strings(&["VSCode"]);
+        assert!(validate_installer(0, &inst).is_err());
+        inst.protocols = Some(vec![format!("a{}", "b".repeat(MAX_PROTOCOL_LENGTH))]);
+        assert!(validate_installer(0, &inst).is_err());
+        inst.protocols = Some((0..=MAX_PROTOCOLS).map(|i| format!("p{}", i)).collect());
+        assert!(validate_installer(0, &inst).is_err());
+
+        inst.protocols = None;
+        inst.file_extensions = strings(&[".txt"]);
+        assert_eq!(
+            validate_installer(0, &inst).unwrap_err(),
+            "Installer 1 (x64): '.txt' in FileExtensions must be lowercase, without the dot"
+        );
+        inst.file_extensions = strings(&["TXT"]);
+        assert!(validate_installer(0, &inst).is_err());
+        inst.file_extensions = Some((0..=MAX_FILE_EXTENSIONS).map(|i| format!("e{}", i)).collect());
+        assert!(validate_installer(0, &inst).is_err());
+    }
+
+    #[test]
+    fn exe_switches_are_never_guessed() {
+        assert_eq!(default_exe_switches(Some("nullsoft")), Some(("/S", "/S")));
+        assert_eq!(default_exe_switches(Some("wix")), None);
+        assert_eq!(default_exe_switches(None), None);
+
+        let mut inst = installer();
+        inst.installer_type = "exe".to_string();
+        assert_eq!(
+            validate_installer(0, &inst).unwrap_err(),
+            "Installer 1 (x64): enter the Silent and SilentWithProgress switches of this exe installer"
+        );
+        inst.installer_switches = Some(InstallerSwitches {
+            silent: Some("-q".to_string()),
+            ..Default::default()
+        });
+        assert!(validate_installer(0, &inst).is_err());
+        inst.installer_switches = Some(InstallerSwitches {
+            silent: Some("-q".to_string()),
+            silent_with_progress: Some("-p".to_string()),
+            ..Default::default()
+        });
+        assert!(validate_installer(0, &inst).is_ok());
+
+        inst.installer_switches = None;
+        inst.installer_technology = Some("squirrel".to_string());
+        assert!(validate_installer(0, &inst).is_ok());
+        inst.installer_type = "zip".to_string();
+        inst.installer_technology = None;
+        inst.nested_installer_type = Some("exe".to_string());
+        assert!(validate_installer(0, &inst).is_err());
+    }
+
+    #[test]
+    fn lists_are_emitted_under_the_installer() {
+        assert_eq!(
+            string_list_yaml("Protocols", &strings(&["vscode", "", "ms-word"]), ""),
+            "  Protocols:\n  - vscode\n  - ms-word\n"
+        );
+        assert_eq!(string_list_yaml("Commands", &strings(&[""]), ""), "");
+        assert_eq!(string_list_yaml("Commands", &None, ""), "");
+    }
+
+    fn expected(code: i64, response: &str, url: Option<&str>) -> ExpectedReturnCode {
+        ExpectedReturnCode {
+            installer_return_code: code,
+            return_response: response.to_string(),
+            return_response_url: url.map(|u| u.to_string()),
+        }
+    }
+
+    #[test]
+    fn return_codes_are_checked_against_the_schema() {
+        let label = "Installer 1 (x64)";
+        let mut inst = installer();
+        inst.installer_success_codes = Some(vec![-2_147_483_648, 4_294_967_295]);
+        inst.expected_return_codes = Some(vec![
+            expected(1602, "cancelledByUser", None),
+            expected(3010, "rebootRequiredToFinish", Some("https://example.com/reboot")),
+        ]);
+        assert!(validate_return_codes(label, &inst).is_ok());
+
+        inst.installer_success_codes = Some(vec![0]);
+        assert_eq!(
+            validate_return_codes(label, &inst).unwrap_err(),
+            "Installer 1 (x64): 0 is not a valid installer return code"
+        );
+        inst.installer_success_codes = Some(vec![4_294_967_296]);
+        assert!(validate_return_codes(label, &inst).is_err());
+        inst.installer_success_codes = Some(vec![-2_147_483_649]);
+        assert!(validate_return_codes(label, &inst).is_err());
+        inst.installer_success_codes = Some(vec![3010, 3010]);
+        assert_eq!(
+            validate_return_codes(label, &inst).unwrap_err(),
+            "Installer 1 (x64): InstallerSuccessCodes lists 3010 twice"
+        );
+        inst.installer_success_codes = Some(vec![1602]);
+        assert_eq!(
+            validate_return_codes(label, &inst).unwrap_err(),
+            "Installer 1 (x64): 1602 is both a success code and an expected return code"
+        );
+        inst.installer_success_codes = None;
+
+        let codes = |entries: Vec<ExpectedReturnCode>| InstallerEntry {
+            expected_return_codes: Some(entries),
+            ..installer()
+        };
+        assert_eq!(
+            validate_return_codes(label, &codes(vec![expected(1, "custom", None), expected(1, "downgrade", None)]))
+                .unwrap_err(),
+            "Installer 1 (x64): ExpectedReturnCodes lists 1 twice"
+        );
+        assert_eq!(
+            validate_return_codes(label, &codes(vec![expected(1, "CancelledByUser", None)])).unwrap_err(),
+            "Installer 1 (x64): 'CancelledByUser' is not a valid ReturnResponse for code 1"
+        );
+        assert!(validate_return_codes(label, &codes(vec![expected(0, "custom", None)])).is_err());
+        assert!(validate_return_codes(label, &codes(vec![expected(1, "custom", Some("HTTP://example.com"))])).is_ok());
+        assert!(validate_return_codes(label, &codes(vec![expected(1, "custom", Some(""))])).is_ok());
+        for url in ["example.com/help", "ftp://example.com", "https://"] {
+            assert_eq!(
+                validate_return_codes(label, &codes(vec![expected(1, "custom", Some(url))])).unwrap_err(),
+                "Installer 1 (x64): the ReturnResponseUrl of code 1 must be an http(s) URL"
+            );
+        }
+        let long = format!("https://example.com/{}", "a".repeat(MAX_RETURN_RESPONSE_URL_LENGTH));
+        assert!(validate_return_codes(label, &codes(vec![expected(1, "custom", Some(&long))])).is_err());
+        let many = (1..=MAX_EXPECTED_RETURN_CODES as i64 + 1).map(|c| expected(c, "custom", None)).collect();
+        assert_eq!(
+            validate_return_codes(label, &codes(many)).unwrap_err(),
+            "Installer 1 (x64): at most 128 ExpectedReturnCodes are allowed"
+        );
+    }
+
+    #[test]
+    fn return_codes_are_emitted_in_order() {
+        let mut inst = installer();
+        assert_eq!(return_codes_yaml(&inst), "");
+        inst.installer_success_codes = Some(vec![-1, 3010]);
+        inst.expected_return_codes = Some(vec![
+            expected(1618, "installInProgress", Some("")),
+            expected(1, "contactSupport", Some("https://example.com/help?code=1#top")),
+        ]);
+        assert_eq!(
+            return_codes_yaml(&inst),
+            concat!(
+                "  InstallerSuccessCodes:\n  - -1\n  - 3010\n",
+                "  ExpectedReturnCodes:\n",
+                "  - InstallerReturnCode: 1618\n    ReturnResponse: installInProgress\n",
+                "  - InstallerReturnCode: 1\n    ReturnResponse: contactSupport\n",
+                "    ReturnResponseUrl: https://example.com/help?code=1#top\n",
+            )
+        );
+    }
+
+    #[test]
+    fn dependencies_are_indented_for_their_level() {
+        let dependencies = Dependencies {
+            windows_features: strings(&["NetFx3", ""]),
+            windows_libraries: None,
+            package_dependencies: Some(vec![
+                PackageDependency {
+                    package_identifier: "Microsoft.VCRedist.2015+.x64".to_string(),
+                    minimum_version: Some("14.20".to_string()),
+                },
+                PackageDependency {
+                    package_identifier: String::new(),
+                    minimum_version: Some("1.0".to_string()),
+                },
+                PackageDependency {
+                    package_identifier: "Microsoft.DotNet.DesktopRuntime.8".to_string(),
+                    minimum_version: Some(String::new()),
+                },
+            ]),
+            external_dependencies: strings(&["Java Runtime Environment 8"]),
+        };
+        let expected = concat!(
+            "Dependencies:\n",
+            "  WindowsFeatures:\n",
+            "  - NetFx3\n",
+            "  PackageDependencies:\n",
+            "  - PackageIdentifier: Microsoft.VCRedist.2015+.x64\n",
+            "    MinimumVersion: 14.20\n",
+            "  - PackageIdentifier: Microsoft.DotNet.DesktopRuntime.8\n",
+            "  ExternalDependencies:\n",
+            "  - Java Runtime Environment 8\n",
+        );
+        assert_eq!(dependencies_yaml(&dependencies, ""), expected);
+        // Under an installer, every line moves in by the installer's indent
+        let indented: String = expected.lines().map(|line| format!("  {}\n", line)).collect();
+        assert_eq!(dependencies_yaml(&dependencies, "  "), indented);
+
+        let libraries = Dependencies {
+            windows_libraries: strings(&["Microsoft.WinJS"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            dependencies_yaml(&libraries, "  "),
+            "  Dependencies:\n    WindowsLibraries:\n    - Microsoft.WinJS\n"
+        );
+        let empty = Dependencies {
+            windows_features: strings(&[""]),
+            package_dependencies: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert_eq!(dependencies_yaml(&empty, ""), "");
+    }
 }
@@ -0,0 +1,93 @@
+import type { ExpectedReturnCode, InstallerEntry, ReturnResponse } from "@/lib/types";
+
+export const returnResponses: ReturnResponse[] = [
+  "packageInUse",
+  "packageInUseByApplication",
+  "installInProgress",
+  "fileInUse",
+  "missingDependency",
+  "diskFull",
+  "insufficientMemory",
+  "invalidParameter",
+  "noNetwork",
+  "contactSupport",
+  "rebootRequiredToFinish",
+  "rebootRequiredForInstall",
+  "rebootInitiated",
+  "cancelledByUser",
+  "alreadyInstalled",
+  "downgrade",
+  "blockedByPolicy",
+  "systemNotSupported",
+  "custom",
+];
+
+interface ReturnCodePreset {
+  label: string;
+  expectedReturnCodes: ExpectedReturnCode[];
+}
+
+/** Windows Installer codes, also surfaced by bundles that chain MSIs. */
+const msiCodes: ExpectedReturnCode[] = [
+  { installerReturnCode: 1602, returnResponse: "cancelledByUser" },
+  { installerReturnCode: 1618, returnResponse: "installInProgress" },
+  { installerReturnCode: 1625, returnResponse: "blockedByPolicy" },
+  { installerReturnCode: 1633, returnResponse: "systemNotSupported" },
+  { installerReturnCode: 1638, returnResponse: "alreadyInstalled" },
+  { installerReturnCode: 1641, returnResponse: "rebootInitiated" },
+  { installerReturnCode: 3010, returnResponse: "rebootRequiredToFinish" },
+];
+
+const presets: Record<string, ReturnCodePreset> = {
+  msi: { label: "MSI", expectedReturnCodes: msiCodes },
+  wix: { label: "MSI", expectedReturnCodes: msiCodes },
+  burn: { label: "Burn", expectedReturnCodes: msiCodes },
+  // https://jrsoftware.org/ishelp/index.php?topic=setupexitcodes
+  inno: {
+    label: "Inno Setup",
+    expectedReturnCodes: [
+      { installerReturnCode: 2, returnResponse: "cancelledByUser" },
+      { installerReturnCode: 5, returnResponse: "cancelledByUser" },
+      { installerReturnCode: 8, returnResponse: "rebootRequiredForInstall" },
+    ],
+  },
+  nullsoft: {
+    label: "NSIS",
+    expectedReturnCodes: [
+      { installerReturnCode: 1, returnResponse: "cancelledByUser" },
+    ],
+  },
+};
+
+/** Preset for the installer's framework, falling back to its type for `exe` wrappers. */
+export function returnCodePreset(installer: InstallerEntry): ReturnCodePreset | undefined {
+  return presets[installer.installerTechnology ?? ""] ?? presets[installer.installerType];
+}
+
+/** Adds the preset's codes, keeping any the user already set for the same exit code. */
+export function withPreset(installer: InstallerEntry, preset: ReturnCodePreset): InstallerEntry {
+  const existing = installer.expectedReturnCodes ?? [];
+  const taken = new Set([...existing.map((c) => c.installerReturnCode), ...(installer.installerSuccessCodes ?? [])]);
+  const added = preset.expectedReturnCodes.filter((c) => !taken.has(c.installerReturnCode));
+  return { ...installer, expectedReturnCodes: [...existing, ...added] };
+}
+
+// Same rules as the generator's validation, so a rejected value never reaches it
+const MIN_RETURN_CODE = -2147483648;
+const MAX_RETURN_CODE = 4294967295;
+const MAX_RETURN_RESPONSE_URL_LENGTH = 2048;
+
+/** A non-zero 32-bit exit code, written signed or unsigned, or `null`. */
+export function parseReturnCode(value: string): number | null {
+  const text = value.trim();
+  if (!/^-?\d{1,10}$/.test(text)) return null;
+  const code = Number(text);
+  return code !== 0 && code >= MIN_RETURN_CODE && code <= MAX_RETURN_CODE ? code : null;
+}
+
+/** An http(s) `ReturnResponseUrl`, `undefined` when empty, or `null` when invalid. */
+export function parseReturnResponseUrl(value: string): string | undefined | null {
+  const url = value.trim();
+  if (!url) return undefined;
+  return url.length <= MAX_RETURN_RESPONSE_URL_LENGTH && /^https?:\/\/.+$/i.test(url) ? url : null;
+}
@@ -0,0 +1,32 @@
+import type { InstallerEntry } from "@/lib/types";
+
+interface SilentSwitches {
+  silent: string;
+  silentWithProgress: string;
+}
+
+/** Switches the manifest generator fills in for `exe` installers of a known framework. */
+const defaults: Record<string, SilentSwitches> = {
+  installShield: { silent: "/s /v\"/qn\"", silentWithProgress: "/s /v\"/qb\"" },
+  squirrel: { silent: "--silent", silentWithProgress: "--silent" },
+  advancedInstaller: { silent: "/exenoui /quiet /norestart", silentWithProgress: "/exenoui /passive /norestart" },
+  nullsoft: { silent: "/S", silentWithProgress: "/S" },
+  inno: { silent: "/VERYSILENT /SUPPRESSMSGBOXES /NORESTART /SP-", silentWithProgress: "/SILENT /SUPPRESSMSGBOXES /NORESTART /SP-" },
+  burn: { silent: "/quiet /norestart", silentWithProgress: "/passive /norestart" },
+};
+
+/** Whether the installer, or the installer nested in its archive, is a plain `exe`. */
+export function runsExe(installer: InstallerEntry): boolean {
+  return installer.installerType === "exe" || (installer.installerType === "zip" && installer.nestedInstallerType === "exe");
+}
+
+export function defaultSwitches(installer: InstallerEntry): SilentSwitches | undefined {
+  return runsExe(installer) ? defaults[installer.installerTechnology ?? ""] : undefined;
+}
+
+/** An `exe` of an unknown framework, still without the switches winget-pkgs requires. */
+export function needsSilentSwitches(installer: InstallerEntry): boolean {
+  if (!runsExe(installer) || defaultSwitches(installer)) return false;
+  const switches = installer.installerSwitches;
+  return !switches?.silent || !switches.silentWithProgress;
+}
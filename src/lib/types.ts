@@ -11,8 +11,18 @@ export type InstallerType =
   | "zip"
   | "portable";
 
+export type InstallerTechnology =
+  | "inno"
+  | "nullsoft"
+  | "burn"
+  | "installShield"
+  | "squirrel"
+  | "advancedInstaller";
+
 export type Scope = "user" | "machine";
 
+export type ArchSource = "peHeader" | "clrHeader" | "msiTemplate" | "appxManifest" | "fileName";
+
 export type InstallMode = "silent" | "silentWithProgress" | "interactive";
 
 export type UpgradeBehavior = "install" | "uninstallPrevious" | "deny";
@@ -22,6 +32,33 @@ export type ElevationRequirement =
   | "elevationProhibited"
   | "elevatesSelf";
 
+export type ReturnResponse =
+  | "packageInUse"
+  | "packageInUseByApplication"
+  | "installInProgress"
+  | "fileInUse"
+  | "missingDependency"
+  | "diskFull"
+  | "insufficientMemory"
+  | "invalidParameter"
+  | "noNetwork"
+  | "contactSupport"
+  | "rebootRequiredToFinish"
+  | "rebootRequiredForInstall"
+  | "rebootInitiated"
+  | "cancelledByUser"
+  | "alreadyInstalled"
+  | "downgrade"
+  | "blockedByPolicy"
+  | "systemNotSupported"
+  | "custom";
+
+export interface ExpectedReturnCode {
+  installerReturnCode: number;
+  returnResponse: ReturnResponse;
+  returnResponseUrl?: string;
+}
+
 export interface InstallerSwitches {
   silent?: string;
   silentWithProgress?: string;
@@ -33,6 +70,32 @@ export interface InstallerSwitches {
   repair?: string;
 }
 
+export interface AppsAndFeaturesEntry {
+  displayName?: string;
+  publisher?: string;
+  displayVersion?: string;
+  productCode?: string;
+  upgradeCode?: string;
+  /** Set when the entry comes from another technology than the installer, e.g. an MSI chained by a bundle. */
+  installerType?: InstallerType;
+}
+
+export interface PackageDependency {
+  packageIdentifier: string;
+  minimumVersion?: string;
+}
+
+export interface InstallationMetadata {
+  defaultInstallLocation?: string;
+}
+
+export interface Dependencies {
+  windowsFeatures?: string[];
+  windowsLibraries?: string[];
+  packageDependencies?: PackageDependency[];
+  externalDependencies?: string[];
+}
+
 export interface InstallerEntry {
   architecture: Architecture;
   installerType: InstallerType;
@@ -40,11 +103,42 @@ export interface InstallerEntry {
   installerSha256: string;
   scope?: Scope;
   installerSwitches?: InstallerSwitches;
+  installerSuccessCodes?: number[];
+  expectedReturnCodes?: ExpectedReturnCode[];
   installModes?: InstallMode[];
   signatureSha256?: string;
   productCode?: string;
+  packageFamilyName?: string;
   upgradeBehavior?: UpgradeBehavior;
   elevationRequirement?: ElevationRequirement;
+  appsAndFeaturesEntries?: AppsAndFeaturesEntry[];
+  commands?: string[];
+  protocols?: string[];
+  fileExtensions?: string[];
+  dependencies?: Dependencies;
+  installationMetadata?: InstallationMetadata;
+  nestedInstallerType?: InstallerType;
+  nestedInstallerFiles?: NestedInstallerFile[];
+  archiveBinariesDependOnPath?: boolean;
+  installerTechnology?: InstallerTechnology;
+  /** Authenticode subject name of the analyzed file; only used for the publisher check. */
+  signerName?: string;
+  /** Result of the release checksum cross-check; a mismatch blocks submission. */
+  checksum?: ChecksumCheck;
+}
+
+/** `unverified` when GitHub refused to list the release, e.g. at its API rate limit. */
+export type ChecksumStatus = "match" | "mismatch" | "unverified";
+
+export interface ChecksumCheck {
+  source: string;
+  expected?: string;
+  status: ChecksumStatus;
+}
+
+export interface NestedInstallerFile {
+  relativeFilePath: string;
+  portableCommandAlias?: string;
 }
 
 export interface LocaleData {
@@ -66,6 +160,26 @@ export interface LocaleData {
   tags?: string[];
   releaseNotes?: string;
   releaseNotesUrl?: string;
+  icons?: Icon[];
+}
+
+export interface Icon {
+  iconUrl: string;
+  iconFileType: "png" | "jpeg" | "ico";
+  iconResolution?: string;
+  iconTheme?: "default" | "light" | "dark" | "highContrast";
+  iconSha256?: string;
+}
+
+export interface IconInfo {
+  fileType: "png" | "ico";
+  resolution: string;
+  theme: "light" | "dark" | "highContrast" | null;
+  fileSize: number;
+  sha256: string;
+  source: string;
+  /** Base64 file contents. */
+  data: string;
 }
 
 export interface ManifestData {
@@ -73,18 +187,163 @@ export interface ManifestData {
   packageVersion: string;
   defaultLocale: string;
   minimumOSVersion?: string;
+  /** Root-level dependencies; an installer's own `dependencies` take precedence. */
+  dependencies?: Dependencies;
   installers: InstallerEntry[];
   locale: LocaleData;
   additionalLocales?: LocaleData[];
 }
 
+export interface MsiInfo {
+  productCode: string | null;
+  upgradeCode: string | null;
+  productVersion: string | null;
+  manufacturer: string | null;
+  productName: string | null;
+  allUsers: string | null;
+  scope: Scope | null;
+  architecture: Architecture | null;
+  languages: string[];
+  fileExtensions: string[];
+  protocols: string[];
+}
+
+export interface MsixInfo {
+  identityName: string | null;
+  publisher: string | null;
+  version: string | null;
+  processorArchitecture: Architecture | null;
+  minOsVersion: string | null;
+  packageFamilyName: string | null;
+  isBundle: boolean;
+  packages: BundlePackage[];
+  packageDependencies: MsixDependency[];
+  fileExtensions: string[];
+  protocols: string[];
+  commands: string[];
+}
+
+export interface MsixDependency {
+  name: string;
+  minVersion: string | null;
+}
+
+export interface DependencySuggestion {
+  packageIdentifier: string;
+  minimumVersion: string | null;
+  /** Why it was suggested, e.g. "imports vcruntime140.dll". */
+  reason: string;
+}
+
+export interface BundlePackage {
+  fileName: string;
+  packageType: string;
+  architecture: Architecture | null;
+  version: string | null;
+}
+
+export interface BurnPackage {
+  id: string;
+  productCode: string | null;
+  upgradeCode: string | null;
+  version: string | null;
+  displayName: string | null;
+  visible: boolean;
+}
+
+export interface BurnInfo {
+  bundleId: string | null;
+  upgradeCode: string | null;
+  providerKey: string | null;
+  version: string | null;
+  displayName: string | null;
+  displayVersion: string | null;
+  publisher: string | null;
+  perMachine: boolean | null;
+  packages: BurnPackage[];
+}
+
+export interface InnoInfo {
+  setupVersion: string;
+  appId: string | null;
+  productCode: string | null;
+  appName: string | null;
+  appVersion: string | null;
+  appPublisher: string | null;
+  defaultDirName: string | null;
+  defaultInstallLocation: string | null;
+  privilegesRequired: "none" | "poweruser" | "admin" | "lowest" | null;
+  privilegesOverrideAllowed: boolean;
+  scope: Scope | null;
+}
+
+export interface SignatureInfo {
+  signed: boolean;
+  subject: string | null;
+  subjectName: string | null;
+  issuer: string | null;
+  thumbprint: string | null;
+  signingTime: string | null;
+}
+
+export interface VersionInfo {
+  companyName: string | null;
+  productName: string | null;
+  fileDescription: string | null;
+  legalCopyright: string | null;
+  productVersion: string | null;
+  fileVersion: string | null;
+  originalFilename: string | null;
+}
+
+export interface ArchiveInfo {
+  entryCount: number;
+  nestedInstallerType: InstallerType | null;
+  nestedInstallerFiles: NestedInstallerFile[];
+  archiveBinariesDependOnPath: boolean;
+  installerTechnology: InstallerTechnology | null;
+}
+
 export interface HashResult {
   sha256: string;
   fileSize: number;
   fileName: string;
   detectedType?: InstallerType;
+  installerTechnology?: InstallerTechnology;
   detectedArch?: Architecture;
+  archSource?: ArchSource;
   signatureSha256?: string;
+  authenticode?: SignatureInfo;
+  msi?: MsiInfo;
+  msix?: MsixInfo;
+  inno?: InnoInfo;
+  burn?: BurnInfo;
+  versionInfo?: VersionInfo;
+  archive?: ArchiveInfo;
+  icons: IconInfo[];
+  dependencies: DependencySuggestion[];
+  commands: string[];
+  protocols: string[];
+  fileExtensions: string[];
+  suggestedInstallers: InstallerEntry[];
+  redirectChain: string[];
+  finalUrl?: string;
+  fromCache: boolean;
+  checksum?: ChecksumCheck;
+}
+
+export interface HashProgress {
+  jobId: string;
+  phase: "downloading" | "hashing" | "analyzing";
+  bytesDone: number;
+  totalBytes: number | null;
+  bytesPerSecond: number;
+}
+
+export interface BatchHashResult {
+  jobId: string;
+  result: HashResult | null;
+  error: string | null;
 }
 
 export interface YamlFile {
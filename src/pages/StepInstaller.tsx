@@ -1,8 +1,10 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { useManifestStore } from "@/stores/manifest-store";
 import { useToastStore } from "@/stores/toast-store";
-import type { Architecture, InstallerType, InstallerEntry, RepoMetadata, HashResult } from "@/lib/types";
+import { useAuthSessionStore } from "@/stores/auth-session-store";
+import type { Architecture, InstallerType, InstallerEntry, AppsAndFeaturesEntry, Dependencies, ExpectedReturnCode, PackageDependency, ReturnResponse, RepoMetadata, HashResult, HashProgress, BatchHashResult } from "@/lib/types";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import {
   Plus,
@@ -18,16 +20,449 @@ import {
   RefreshCw,
   FolderOpen,
   Upload,
+  X,
+  ChevronDown,
 } from "lucide-react";
 import { cn } from "@/lib/utils";
+import { parseReturnCode, parseReturnResponseUrl, returnCodePreset, returnResponses, withPreset } from "@/lib/return-codes";
+import { defaultSwitches, needsSilentSwitches, runsExe } from "@/lib/switches";
 
 const architectures: Architecture[] = ["x64", "x86", "arm64", "arm", "neutral"];
 const installerTypes: InstallerType[] = [
   "exe", "msi", "msix", "inno", "nullsoft", "wix", "burn", "zip", "portable",
 ];
 
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(0)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
+}
+
+function progressLabel(p: HashProgress): string {
+  if (p.phase === "analyzing") return "Analyzing installer...";
+  const verb = p.phase === "downloading" ? "Downloading" : "Hashing";
+  const amount = p.totalBytes
+    ? `${Math.floor((p.bytesDone / p.totalBytes) * 100)}% of ${formatBytes(p.totalBytes)}`
+    : formatBytes(p.bytesDone);
+  const speed = p.bytesPerSecond > 0 ? ` · ${formatBytes(p.bytesPerSecond)}/s` : "";
+  return `${verb} ${amount}${speed}`;
+}
+
+/** Error the hash commands return, unchanged, for a cancelled job. */
+const HASH_JOB_CANCELLED = "HASH_JOB_CANCELLED";
+const isCancelled = (e: unknown) => e === HASH_JOB_CANCELLED;
+
+function dependencyFields(result: HashResult): Partial<InstallerEntry> {
+  if (!result.dependencies?.length) return {};
+  return {
+    dependencies: {
+      packageDependencies: result.dependencies.map((d) => ({
+        packageIdentifier: d.packageIdentifier,
+        minimumVersion: d.minimumVersion || undefined,
+      })),
+    },
+  };
+}
+
+function analysisFields(result: HashResult): Partial<InstallerEntry> {
+  if (result.msix?.packageFamilyName) {
+    return { packageFamilyName: result.msix.packageFamilyName };
+  }
+  const archive = result.archive;
+  if (archive?.nestedInstallerType) {
+    return {
+      nestedInstallerType: archive.nestedInstallerType,
+      nestedInstallerFiles: archive.nestedInstallerFiles.map((f) => ({
+        relativeFilePath: f.relativeFilePath,
+        portableCommandAlias: f.portableCommandAlias || undefined,
+      })),
+      archiveBinariesDependOnPath: archive.archiveBinariesDependOnPath || undefined,
+      installerTechnology: archive.installerTechnology || undefined,
+    };
+  }
+  const inno = result.inno;
+  if (inno) {
+    return {
+      productCode: inno.productCode || undefined,
+      scope: inno.scope || undefined,
+      installationMetadata: inno.defaultInstallLocation
+        ? { defaultInstallLocation: inno.defaultInstallLocation }
+        : undefined,
+    };
+  }
+  const burn = result.burn;
+  if (burn) {
+    // The bundle owns the Apps & Features entry; chained MSIs only show up when visible
+    const bundleEntry = {
+      displayName: burn.displayName || undefined,
+      publisher: burn.publisher || undefined,
+      displayVersion: burn.displayVersion || burn.version || undefined,
+      productCode: burn.bundleId || undefined,
+      upgradeCode: burn.upgradeCode || undefined,
+    };
+    const packageEntries = burn.packages
+      .filter((p) => p.visible && p.productCode)
+      .map((p) => ({
+        displayName: p.displayName || undefined,
+        displayVersion: p.version || undefined,
+        productCode: p.productCode || undefined,
+        upgradeCode: p.upgradeCode || undefined,
+        installerType: "msi" as const,
+      }));
+    return {
+      productCode: burn.bundleId || undefined,
+      scope: burn.perMachine == null ? undefined : burn.perMachine ? "machine" : "user",
+      appsAndFeaturesEntries: [bundleEntry, ...packageEntries],
+    };
+  }
+  const msi = result.msi;
+  if (!msi) return {};
+  return {
+    productCode: msi.productCode || undefined,
+    scope: msi.scope || undefined,
+    appsAndFeaturesEntries: [{
+      displayName: msi.productName || undefined,
+      publisher: msi.manufacturer || undefined,
+      displayVersion: msi.productVersion || undefined,
+      productCode: msi.productCode || undefined,
+      upgradeCode: msi.upgradeCode || undefined,
+    }],
+  };
+}
+
+const inputClass = "h-8 w-full rounded-lg border border-border bg-background/50 px-3 text-[12px] placeholder:text-muted-foreground/40 focus:border-primary/50 focus:outline-none focus:ring-1 focus:ring-primary/20 transition-all";
+
+function ListInput({ label, value, placeholder, onChange }: { label: string; value: string[]; placeholder: string; onChange: (values: string[]) => void }) {
+  const [input, setInput] = useState("");
+  const add = () => { const item = input.trim(); if (item && !value.includes(item)) { onChange([...value, item]); setInput(""); } };
+  return (
+    <div className="space-y-1">
+      <label className="text-[12px] font-medium text-foreground/70">{label}</label>
+      {value.length > 0 && (
+        <div className="flex flex-wrap gap-1">
+          {value.map((item) => (
+            <span key={item} className="flex items-center gap-1 rounded-md bg-primary/8 px-2 py-0.5 text-[11px] font-medium text-primary/80">
+              {item}
+              <button onClick={() => onChange(value.filter((v) => v !== item))} className="hover:text-destructive"><X className="h-2.5 w-2.5" /></button>
+            </span>
+          ))}
+        </div>
+      )}
+      <input value={input} onChange={(e) => setInput(e.target.value)} onKeyDown={(e) => e.key === "Enter" && (e.preventDefault(), add())}
+        placeholder={placeholder} className={inputClass} />
+    </div>
+  );
+}
+
+function PackageDependencyInput({ value, onChange }: { value: PackageDependency[]; onChange: (values: PackageDependency[]) => void }) {
+  const [id, setId] = useState("");
+  const [minimumVersion, setMinimumVersion] = useState("");
+  const add = () => {
+    const packageIdentifier = id.trim();
+    if (!packageIdentifier || value.some((d) => d.packageIdentifier === packageIdentifier)) return;
+    onChange([...value, { packageIdentifier, minimumVersion: minimumVersion.trim() || undefined }]);
+    setId("");
+    setMinimumVersion("");
+  };
+  const onKeyDown = (e: React.KeyboardEvent) => e.key === "Enter" && (e.preventDefault(), add());
+  return (
+    <div className="space-y-1">
+      <label className="text-[12px] font-medium text-foreground/70">Package dependencies</label>
+      {value.length > 0 && (
+        <div className="flex flex-wrap gap-1">
+          {value.map((dep) => (
+            <span key={dep.packageIdentifier} className="flex items-center gap-1 rounded-md bg-amber-500/10 px-2 py-0.5 text-[11px] font-medium text-amber-400">
+              {dep.packageIdentifier}{dep.minimumVersion ? ` ≥ ${dep.minimumVersion}` : ""}
+              <button onClick={() => onChange(value.filter((d) => d.packageIdentifier !== dep.packageIdentifier))} className="hover:text-destructive"><X className="h-2.5 w-2.5" /></button>
+            </span>
+          ))}
+        </div>
+      )}
+      <div className="grid grid-cols-[2fr_1fr] gap-2">
+        <input value={id} onChange={(e) => setId(e.target.value)} onKeyDown={onKeyDown} placeholder="Microsoft.VCRedist.2015+.x64" className={inputClass} />
+        <input value={minimumVersion} onChange={(e) => setMinimumVersion(e.target.value)} onKeyDown={onKeyDown} placeholder="Minimum version" className={inputClass} />
+      </div>
+    </div>
+  );
+}
+
+/** `Silent` and `SilentWithProgress` of an `exe`; required unless its framework has known ones. */
+function SwitchesEditor({ installer, onChange }: { installer: InstallerEntry; onChange: (installer: InstallerEntry) => void }) {
+  const defaults = defaultSwitches(installer);
+  const missing = needsSilentSwitches(installer);
+  const setSwitch = (field: "silent" | "silentWithProgress", value: string) =>
+    onChange({ ...installer, installerSwitches: { ...installer.installerSwitches, [field]: value.trim() ? value : undefined } });
+  const fields = [
+    { field: "silent", label: "Silent" },
+    { field: "silentWithProgress", label: "SilentWithProgress" },
+  ] as const;
+
+  return (
+    <div className="space-y-1 pt-0.5">
+      <span className={cn("text-[11px]", missing ? "text-destructive" : "text-muted-foreground")}>
+        {defaults ? "Switches (detected defaults apply when empty)" : "Switches (required for exe installers)"}
+      </span>
+      <div className="grid grid-cols-2 gap-1.5">
+        {fields.map(({ field, label }) => (
+          <input key={field} value={installer.installerSwitches?.[field] ?? ""} onChange={(e) => setSwitch(field, e.target.value)}
+            placeholder={defaults?.[field] ?? label} title={label}
+            className={cn(
+              "h-7 w-full rounded-md border bg-background/50 px-2 font-mono text-[11px] placeholder:text-muted-foreground/40 focus:border-primary/50 focus:outline-none",
+              missing && !installer.installerSwitches?.[field] ? "border-destructive/50" : "border-border"
+            )} />
+        ))}
+      </div>
+    </div>
+  );
+}
+
+/** `InstallerSuccessCodes` and `ExpectedReturnCodes` of one installer, with its technology's preset. */
+function ReturnCodesEditor({ installer, onChange }: { installer: InstallerEntry; onChange: (installer: InstallerEntry) => void }) {
+  const [code, setCode] = useState("");
+  const [response, setResponse] = useState<ReturnResponse | "success">("success");
+  const [url, setUrl] = useState("");
+  const success = installer.installerSuccessCodes ?? [];
+  const expected = installer.expectedReturnCodes ?? [];
+  const preset = returnCodePreset(installer);
+  const presetPending = preset && (withPreset(installer, preset).expectedReturnCodes?.length ?? 0) > expected.length;
+
+  const value = parseReturnCode(code);
+  const responseUrl = response === "success" ? undefined : parseReturnResponseUrl(url);
+  const codeInvalid = code.trim() !== "" && value === null;
+  const urlInvalid = responseUrl === null;
+
+  // Adding a code that is already listed replaces its entry
+  const add = () => {
+    if (value === null || responseUrl === null) return;
+    const otherSuccess = success.filter((c) => c !== value);
+    const otherExpected = expected.filter((c) => c.installerReturnCode !== value);
+    onChange(response === "success"
+      ? { ...installer, installerSuccessCodes: [...otherSuccess, value], expectedReturnCodes: otherExpected.length ? otherExpected : undefined }
+      : {
+          ...installer,
+          installerSuccessCodes: otherSuccess.length ? otherSuccess : undefined,
+          expectedReturnCodes: [...otherExpected, { installerReturnCode: value, returnResponse: response, returnResponseUrl: responseUrl }],
+        });
+    setCode("");
+    setUrl("");
+  };
+  const edit = (entry: ExpectedReturnCode) => {
+    setCode(String(entry.installerReturnCode));
+    setResponse(entry.returnResponse);
+    setUrl(entry.returnResponseUrl ?? "");
+  };
+  const removeSuccess = (value: number) => {
+    const rest = success.filter((c) => c !== value);
+    onChange({ ...installer, installerSuccessCodes: rest.length ? rest : undefined });
+  };
+  const removeExpected = (value: number) => {
+    const rest = expected.filter((c) => c.installerReturnCode !== value);
+    onChange({ ...installer, expectedReturnCodes: rest.length ? rest : undefined });
+  };
+
+  return (
+    <div className="space-y-1 pt-0.5">
+      <div className="flex flex-wrap items-center gap-1">
+        <span className="text-[11px] text-muted-foreground">Return codes</span>
+        {success.map((value) => (
+          <span key={`s${value}`} className="flex items-center gap-1 rounded bg-emerald-500/10 px-1.5 py-0.5 text-[10px] font-medium text-emerald-400">
+            {value} → success
+            <button onClick={() => removeSuccess(value)} className="hover:text-destructive" title="Remove code"><X className="h-2.5 w-2.5" /></button>
+          </span>
+        ))}
+        {expected.map((c) => (
+          <span key={`e${c.installerReturnCode}`} className="flex items-center gap-1 rounded bg-secondary/80 px-1.5 py-0.5 text-[10px] font-medium text-secondary-foreground/70">
+            <button onClick={() => edit(c)} className="flex items-center gap-1 hover:text-foreground" title={c.returnResponseUrl ?? "Edit code"}>
+              {c.installerReturnCode} → {c.returnResponse}
+              {c.returnResponseUrl && <Link2 className="h-2.5 w-2.5" />}
+            </button>
+            <button onClick={() => removeExpected(c.installerReturnCode)} className="hover:text-destructive" title="Remove code"><X className="h-2.5 w-2.5" /></button>
+          </span>
+        ))}
+        {preset && presetPending && (
+          <button onClick={() => onChange(withPreset(installer, preset))} className="rounded px-1.5 py-0.5 text-[10px] font-medium text-primary hover:bg-primary/10">
+            + {preset.label} codes
+          </button>
+        )}
+      </div>
+      <div className="flex items-center gap-1.5">
+        <input value={code} onChange={(e) => setCode(e.target.value)} onKeyDown={(e) => e.key === "Enter" && (e.preventDefault(), add())}
+          placeholder="Exit code" inputMode="numeric" title={codeInvalid ? "A non-zero 32-bit integer" : undefined}
+          className={cn("h-7 w-24 rounded-md border bg-background/50 px-2 text-[11px] placeholder:text-muted-foreground/40 focus:border-primary/50 focus:outline-none",
+            codeInvalid ? "border-destructive/50" : "border-border")} />
+        <select value={response} onChange={(e) => setResponse(e.target.value as ReturnResponse | "success")}
+          className="h-7 rounded-md border border-border bg-background/50 px-2 text-[11px] focus:border-primary/50 focus:outline-none">
+          <option value="success">success</option>
+          {returnResponses.map((r) => (
+            <option key={r} value={r}>{r}</option>
+          ))}
+        </select>
+        {response !== "success" && (
+          <input value={url} onChange={(e) => setUrl(e.target.value)} onKeyDown={(e) => e.key === "Enter" && (e.preventDefault(), add())}
+            placeholder="Response URL (optional)" title={urlInvalid ? "An http(s) URL" : undefined}
+            className={cn("h-7 min-w-0 flex-1 rounded-md border bg-background/50 px-2 text-[11px] placeholder:text-muted-foreground/40 focus:border-primary/50 focus:outline-none",
+              urlInvalid ? "border-destructive/50" : "border-border")} />
+        )}
+        <button onClick={add} disabled={value === null || urlInvalid} className="rounded-md p-1 text-muted-foreground hover:bg-accent hover:text-foreground disabled:opacity-40" title="Add return code">
+          <Plus className="h-3 w-3" />
+        </button>
+      </div>
+    </div>
+  );
+}
+
+// Same rules as the generator's validation, so a rejected value never reaches it
+const normalizeCommand = (value: string) => {
+  const command = value.trim();
+  return command && command.length <= 40 ? command : null;
+};
+const normalizeProtocol = (value: string) => {
+  const protocol = value.trim().toLowerCase();
+  return protocol.length <= 2048 && /^[a-z][-a-z0-9.+]*$/.test(protocol) ? protocol : null;
+};
+const normalizeFileExtension = (value: string) => {
+  const extension = value.trim().replace(/^\.+/, "").toLowerCase();
+  return extension && extension.length <= 64 && !/[\\/:*?"<>|\x00-\x1f]/.test(extension) ? extension : null;
+};
+
+/** One of an installer's string lists (`Commands`, `Protocols`, `FileExtensions`), as removable chips. */
+function InstallerListEditor({ label, values, placeholder, normalize, format = (v) => v, onChange }: {
+  label: string;
+  values: string[] | undefined;
+  placeholder: string;
+  normalize: (value: string) => string | null;
+  format?: (value: string) => string;
+  onChange: (values: string[] | undefined) => void;
+}) {
+  const [input, setInput] = useState("");
+  const list = values ?? [];
+  const add = () => {
+    const value = normalize(input);
+    if (!value || list.includes(value)) return;
+    onChange([...list, value]);
+    setInput("");
+  };
+  const remove = (value: string) => {
+    const rest = list.filter((v) => v !== value);
+    onChange(rest.length ? rest : undefined);
+  };
+  return (
+    <div className="flex flex-wrap items-center gap-1 pt-0.5">
+      <span className="text-[11px] text-muted-foreground">{label}</span>
+      {list.map((value) => (
+        <span key={value} className="flex items-center gap-1 rounded bg-secondary/80 px-1.5 py-0.5 font-mono text-[10px] font-medium text-secondary-foreground/70">
+          {format(value)}
+          <button onClick={() => remove(value)} className="hover:text-destructive" title="Remove"><X className="h-2.5 w-2.5" /></button>
+        </span>
+      ))}
+      <input value={input} onChange={(e) => setInput(e.target.value)} onKeyDown={(e) => e.key === "Enter" && (e.preventDefault(), add())}
+        placeholder={placeholder} className="h-6 w-28 rounded-md border border-border bg-background/50 px-2 text-[10px] placeholder:text-muted-foreground/40 focus:border-primary/50 focus:outline-none" />
+    </div>
+  );
+}
+
+const normalizeDependency = (value: string) => value.trim() || null;
+
+/** The installer's own `WindowsFeatures`, `WindowsLibraries` and `ExternalDependencies`. */
+function InstallerDependenciesEditor({ installer, onChange }: { installer: InstallerEntry; onChange: (installer: InstallerEntry) => void }) {
+  const dependencies = installer.dependencies;
+  const update = (patch: Partial<Dependencies>) => {
+    const next = { ...dependencies, ...patch };
+    const empty = Object.values(next).every((list) => !list?.length);
+    onChange({ ...installer, dependencies: empty ? undefined : next });
+  };
+  return (
+    <>
+      <InstallerListEditor label="Windows features" values={dependencies?.windowsFeatures} placeholder="Feature, then Enter" normalize={normalizeDependency}
+        onChange={(windowsFeatures) => update({ windowsFeatures })} />
+      <InstallerListEditor label="Windows libraries" values={dependencies?.windowsLibraries} placeholder="Library, then Enter" normalize={normalizeDependency}
+        onChange={(windowsLibraries) => update({ windowsLibraries })} />
+      <InstallerListEditor label="External dependencies" values={dependencies?.externalDependencies} placeholder="Name, then Enter" normalize={normalizeDependency}
+        onChange={(externalDependencies) => update({ externalDependencies })} />
+    </>
+  );
+}
+
+const appsAndFeaturesFields = ["displayName", "publisher", "displayVersion", "productCode", "upgradeCode"] as const;
+const appsAndFeaturesLabels: Record<(typeof appsAndFeaturesFields)[number], string> = {
+  displayName: "DisplayName",
+  publisher: "Publisher",
+  displayVersion: "DisplayVersion",
+  productCode: "ProductCode",
+  upgradeCode: "UpgradeCode",
+};
+
+/** `AppsAndFeaturesEntries` of one installer, for when Apps & Features shows other values than the manifest. */
+function AppsAndFeaturesEditor({ installer, onChange }: { installer: InstallerEntry; onChange: (installer: InstallerEntry) => void }) {
+  const entries = installer.appsAndFeaturesEntries ?? [];
+  const update = (next: AppsAndFeaturesEntry[]) => onChange({ ...installer, appsAndFeaturesEntries: next.length ? next : undefined });
+  const setField = (index: number, field: (typeof appsAndFeaturesFields)[number], value: string) =>
+    update(entries.map((entry, i) => (i === index ? { ...entry, [field]: value || undefined } : entry)));
+
+  return (
+    <div className="space-y-1 pt-0.5">
+      <div className="flex items-center gap-1">
+        <span className="text-[11px] text-muted-foreground">Apps & Features</span>
+        <button onClick={() => update([...entries, {}])} className="rounded px-1.5 py-0.5 text-[10px] font-medium text-primary hover:bg-primary/10">
+          + entry
+        </button>
+      </div>
+      {entries.map((entry, index) => (
+        <div key={index} className="flex items-start gap-1.5">
+          <div className="grid flex-1 grid-cols-3 gap-1.5">
+            {appsAndFeaturesFields.map((field) => (
+              <input key={field} value={entry[field] ?? ""} onChange={(e) => setField(index, field, e.target.value)}
+                placeholder={appsAndFeaturesLabels[field]} title={appsAndFeaturesLabels[field]}
+                className="h-7 w-full rounded-md border border-border bg-background/50 px-2 text-[11px] placeholder:text-muted-foreground/40 focus:border-primary/50 focus:outline-none" />
+            ))}
+            {entry.installerType && (
+              <span className="self-center text-[10px] text-muted-foreground">Installed as {entry.installerType}</span>
+            )}
+          </div>
+          <button onClick={() => update(entries.filter((_, i) => i !== index))} className="rounded-md p-1 text-muted-foreground hover:bg-destructive/10 hover:text-destructive" title="Remove entry">
+            <X className="h-3 w-3" />
+          </button>
+        </div>
+      ))}
+    </div>
+  );
+}
+
+/** Root-level `Dependencies`, applied to every installer that has none of its own. */
+function DependenciesEditor({ value, onChange }: { value: Dependencies | undefined; onChange: (dependencies: Dependencies | undefined) => void }) {
+  const [open, setOpen] = useState(false);
+  const update = (patch: Partial<Dependencies>) => {
+    const next = { ...value, ...patch };
+    const empty = Object.values(next).every((list) => !list?.length);
+    onChange(empty ? undefined : next);
+  };
+  const count = Object.values(value ?? {}).reduce((n, list) => n + (list?.length ?? 0), 0);
+  return (
+    <section className="rounded-xl border border-border bg-card/50 overflow-hidden">
+      <button onClick={() => setOpen(!open)} className="flex w-full items-center justify-between px-5 py-3.5 text-left transition-colors hover:bg-accent/30">
+        <h3 className="text-[11px] font-bold uppercase tracking-widest text-muted-foreground">
+          Dependencies{count > 0 ? ` (${count})` : ""}
+        </h3>
+        <ChevronDown className={cn("h-3.5 w-3.5 text-muted-foreground transition-transform duration-200", open && "rotate-180")} />
+      </button>
+      {open && (
+        <div className="space-y-3 border-t border-border px-5 py-4 animate-fade-in">
+          <p className="text-[11px] text-muted-foreground">Applies to every installer; an installer with dependencies of its own uses only those.</p>
+          <PackageDependencyInput value={value?.packageDependencies ?? []} onChange={(packageDependencies) => update({ packageDependencies })} />
+          <ListInput label="Windows features" value={value?.windowsFeatures ?? []} placeholder="e.g. NetFx3, then Enter" onChange={(windowsFeatures) => update({ windowsFeatures })} />
+          <ListInput label="Windows libraries" value={value?.windowsLibraries ?? []} placeholder="Library name, then Enter" onChange={(windowsLibraries) => update({ windowsLibraries })} />
+          <ListInput label="External dependencies" value={value?.externalDependencies ?? []} placeholder="e.g. Java Runtime Environment 8, then Enter" onChange={(externalDependencies) => update({ externalDependencies })} />
+        </div>
+      )}
+    </section>
+  );
+}
+
 export function StepInstaller() {
-  const { manifest, addInstaller, removeInstaller, setStep, isAnalyzing, setIsAnalyzing, applyRepoMetadata, setPackageVersion, setLocale, isUpdate } = useManifestStore();
+  const { manifest, addInstaller, removeInstaller, updateInstaller, setStep, isAnalyzing, setIsAnalyzing, applyRepoMetadata, applyVersionInfo, setPackageVersion, setLocale, setMinimumOSVersion, setDependencies, isUpdate, addDetectedIcons } = useManifestStore();
+  // Lets the release checksum lookup use the user's API quota instead of the anonymous one
+  const githubToken = useAuthSessionStore((s) => s.activeSessionToken);
   const addToast = useToastStore((s) => s.addToast);
 
   const [url, setUrl] = useState("");
@@ -37,6 +472,37 @@ export function StepInstaller() {
   const [autoFilled, setAutoFilled] = useState(false);
   const [isDragging, setIsDragging] = useState(false);
   const [localHash, setLocalHash] = useState<HashResult | null>(null);
+  const [progress, setProgress] = useState<HashProgress | null>(null);
+  const [batchSize, setBatchSize] = useState(0);
+  const jobsRef = useRef<string[]>([]);
+
+  useEffect(() => {
+    const unlisten = listen<HashProgress>("hash-progress", (event) => {
+      // Per-byte progress only makes sense for a single job
+      if (jobsRef.current.length === 1 && event.payload.jobId === jobsRef.current[0]) setProgress(event.payload);
+    });
+    return () => { unlisten.then((f) => f()); };
+  }, []);
+
+  const startJobs = (count: number) => {
+    const ids = Array.from({ length: count }, () => crypto.randomUUID());
+    jobsRef.current = ids;
+    setBatchSize(count);
+    setProgress(null);
+    return ids;
+  };
+
+  const startJob = () => startJobs(1)[0];
+
+  const endJob = () => {
+    jobsRef.current = [];
+    setBatchSize(0);
+    setProgress(null);
+  };
+
+  const handleCancel = () => {
+    jobsRef.current.forEach((jobId) => invoke("cancel_hash_job", { jobId }));
+  };
 
   useEffect(() => {
     let unlisten: (() => void) | undefined;
@@ -64,6 +530,11 @@ export function StepInstaller() {
     return () => { unlisten?.(); };
   }, []);
 
+  const applyMinimumOS = (result: HashResult) => {
+    const minVersion = result.msix?.minOsVersion;
+    if (minVersion && !manifest.minimumOSVersion) setMinimumOSVersion(minVersion);
+  };
+
   const isGitHubUrl = (u: string) => u.includes("github.com/");
 
   const handleLocalFile = async (filePath: string) => {
@@ -71,63 +542,135 @@ export function StepInstaller() {
     setError(null);
     setLocalHash(null);
     try {
-      const result = await invoke<HashResult>("hash_local_file", { path: filePath });
+      const result = await invoke<HashResult>("hash_local_file", { path: filePath, jobId: startJob() });
       setLocalHash(result);
       if (result.detectedType) setInstallerType(result.detectedType);
       if (result.detectedArch) setArch(result.detectedArch);
       addToast(`Hash computed: ${result.fileName}`, "success");
     } catch (e) {
-      setError(String(e));
+      if (!isCancelled(e)) setError(String(e));
     } finally {
+      endJob();
       setIsAnalyzing(false);
     }
   };
 
+  const addAnalyzedInstaller = (result: HashResult, installerUrl: string) => {
+    const detectedType = result.detectedType as InstallerType | null;
+    const detectedArch = result.detectedArch as Architecture | null;
+    const entry: InstallerEntry = {
+      architecture: detectedArch || arch,
+      installerType: detectedType || installerType,
+      installerUrl,
+      installerSha256: result.sha256,
+      signatureSha256: result.signatureSha256 || undefined,
+      installerTechnology: result.installerTechnology,
+      signerName: result.authenticode?.subjectName || undefined,
+      checksum: result.checksum || undefined,
+      commands: result.commands?.length ? result.commands : undefined,
+      protocols: result.protocols?.length ? result.protocols : undefined,
+      fileExtensions: result.fileExtensions?.length ? result.fileExtensions : undefined,
+      ...analysisFields(result),
+      ...dependencyFields(result),
+    };
+    if (result.suggestedInstallers.length > 0) {
+      result.suggestedInstallers.forEach((suggested) => addInstaller({ ...suggested, installerUrl, signerName: entry.signerName, checksum: entry.checksum, dependencies: entry.dependencies }));
+    } else {
+      addInstaller(entry);
+    }
+    applyMinimumOS(result);
+    if (detectedType) setInstallerType(detectedType);
+    if (detectedArch) setArch(detectedArch);
+    addDetectedIcons(result.icons);
+    if (result.checksum?.status === "mismatch") {
+      addToast(`SHA256 differs from ${result.checksum.source} in the release`, "error");
+    }
+    announceDependencies(result);
+  };
+
+  const announceDependencies = (result: HashResult) => {
+    if (!result.dependencies?.length) return;
+    const ids = result.dependencies.map((d) => d.packageIdentifier).join(", ");
+    addToast(`Suggested dependencies: ${ids}. Review them before submitting.`, "info");
+  };
+
+  const removeDependency = (index: number, packageIdentifier: string) => {
+    const installer = manifest.installers[index];
+    const remaining = installer.dependencies?.packageDependencies?.filter((d) => d.packageIdentifier !== packageIdentifier) ?? [];
+    const dependencies = { ...installer.dependencies, packageDependencies: remaining.length > 0 ? remaining : undefined };
+    const empty = Object.values(dependencies).every((list) => !list?.length);
+    updateInstaller(index, { ...installer, dependencies: empty ? undefined : dependencies });
+  };
+
+  const applyAnalysisMetadata = (result: HashResult, meta: RepoMetadata | null) => {
+    if (meta) {
+      if (isUpdate) {
+        // In update mode: only apply new release info (version, release notes)
+        if (meta.version) setPackageVersion(meta.version);
+        if (meta.releaseNotes) setLocale({ releaseNotes: meta.releaseNotes });
+        if (meta.releaseUrl) setLocale({ releaseNotesUrl: meta.releaseUrl });
+      } else {
+        applyRepoMetadata(meta);
+        setAutoFilled(true);
+      }
+    } else if (result.versionInfo && !isUpdate) {
+      // Not on GitHub: fall back to the strings embedded in the executable
+      applyVersionInfo(result.versionInfo);
+    }
+  };
+
   const handleAnalyze = async () => {
-    if (!url.trim()) return;
+    const urls = url.trim().split(/\s+/).filter(Boolean);
+    if (urls.length === 0) return;
     setIsAnalyzing(true);
     setError(null);
     setAutoFilled(false);
     setLocalHash(null);
     try {
-      const hashPromise = invoke<HashResult>("download_and_hash", { url: url.trim() });
-
-      const metaPromise = isGitHubUrl(url.trim())
-        ? invoke<RepoMetadata>("fetch_repo_metadata", { url: url.trim() }).catch(() => null)
+      const githubUrl = urls.find(isGitHubUrl);
+      const metaPromise = githubUrl
+        ? invoke<RepoMetadata>("fetch_repo_metadata", { url: githubUrl }).catch(() => null)
         : Promise.resolve(null);
 
-      const [result, meta] = await Promise.all([hashPromise, metaPromise]);
-
-      const detectedType = result.detectedType as InstallerType | null;
-      const detectedArch = result.detectedArch as Architecture | null;
-      const entry: InstallerEntry = {
-        architecture: detectedArch || arch,
-        installerType: detectedType || installerType,
-        installerUrl: url.trim(),
-        installerSha256: result.sha256,
-        signatureSha256: result.signatureSha256 || undefined,
-      };
-      addInstaller(entry);
-      if (detectedType) setInstallerType(detectedType);
-      if (detectedArch) setArch(detectedArch);
-
-      if (meta) {
-        if (isUpdate) {
-          // In update mode: only apply new release info (version, release notes)
-          if (meta.version) setPackageVersion(meta.version);
-          if (meta.releaseNotes) setLocale({ releaseNotes: meta.releaseNotes });
-          if (meta.releaseUrl) setLocale({ releaseNotesUrl: meta.releaseUrl });
-        } else {
-          applyRepoMetadata(meta);
-          setAutoFilled(true);
+      if (urls.length > 1) {
+        // Several URLs pasted at once: hash them together, keeping the ones that worked
+        const jobIds = startJobs(urls.length);
+        const [results, meta] = await Promise.all([
+          invoke<BatchHashResult[]>("hash_batch", { items: urls.map((u, i) => ({ jobId: jobIds[i], url: u })), token: githubToken }),
+          metaPromise,
+        ]);
+        const succeeded = results.filter((r) => r.result);
+        succeeded.forEach((r) => addAnalyzedInstaller(r.result!, urls[jobIds.indexOf(r.jobId)]));
+        const failed = results.filter((r) => r.error && !isCancelled(r.error));
+        if (failed.length > 0) {
+          setError(failed.map((r) => `${urls[jobIds.indexOf(r.jobId)]}: ${r.error}`).join("\n"));
         }
+        if (succeeded.length === 0) return;
+        applyAnalysisMetadata(succeeded[0].result!, meta);
+        addToast(`${succeeded.length} of ${urls.length} installers added`, failed.length > 0 ? "error" : "success");
+        setUrl(failed.map((r) => urls[jobIds.indexOf(r.jobId)]).join(" "));
+        return;
       }
 
-      addToast("Installer added successfully", "success");
+      const [result, meta] = await Promise.all([
+        invoke<HashResult>("download_and_hash", { url: urls[0], jobId: startJob(), token: githubToken }),
+        metaPromise,
+      ]);
+      addAnalyzedInstaller(result, urls[0]);
+      applyAnalysisMetadata(result, meta);
+
+      if (result.checksum?.status === "match") {
+        addToast(`Installer added, SHA256 matches ${result.checksum.source}`, "success");
+      } else if (result.checksum?.status === "unverified") {
+        addToast("Installer added; GitHub refused the release lookup, so its checksums were not verified", "info");
+      } else if (!result.checksum) {
+        addToast(result.fromCache ? "Installer added (unchanged since last analysis)" : "Installer added successfully", "success");
+      }
       setUrl("");
     } catch (e) {
-      setError(String(e));
+      if (!isCancelled(e)) setError(String(e));
     } finally {
+      endJob();
       setIsAnalyzing(false);
     }
   };
@@ -140,14 +683,30 @@ export function StepInstaller() {
       installerUrl: url.trim(),
       installerSha256: localHash.sha256,
       signatureSha256: localHash.signatureSha256 || undefined,
+      installerTechnology: localHash.installerTechnology,
+      signerName: localHash.authenticode?.subjectName || undefined,
+      commands: localHash.commands?.length ? localHash.commands : undefined,
+      protocols: localHash.protocols?.length ? localHash.protocols : undefined,
+      fileExtensions: localHash.fileExtensions?.length ? localHash.fileExtensions : undefined,
+      ...analysisFields(localHash),
+      ...dependencyFields(localHash),
     };
-    addInstaller(entry);
+    if (localHash.suggestedInstallers.length > 0) {
+      localHash.suggestedInstallers.forEach((suggested) => addInstaller({ ...suggested, installerUrl: url.trim(), signerName: entry.signerName, dependencies: entry.dependencies }));
+    } else {
+      addInstaller(entry);
+    }
+    applyMinimumOS(localHash);
+    addDetectedIcons(localHash.icons);
+    announceDependencies(localHash);
+    if (localHash.versionInfo && !isUpdate) applyVersionInfo(localHash.versionInfo);
     setLocalHash(null);
     setUrl("");
     addToast("Installer added from local file", "success");
   };
 
-  const canProceed = manifest.installers.length > 0;
+  const missingSwitches = manifest.installers.some(needsSilentSwitches);
+  const canProceed = manifest.installers.length > 0 && !missingSwitches;
 
   return (
     <div className="space-y-7">
@@ -274,7 +833,9 @@ export function StepInstaller() {
                 {isAnalyzing ? (
                   <>
                     <Loader2 className="h-3.5 w-3.5 animate-spin" />
-                    Downloading & computing hash...
+                    {batchSize > 1
+                      ? `Downloading & hashing ${batchSize} installers...`
+                      : progress ? progressLabel(progress) : "Downloading & computing hash..."}
                   </>
                 ) : (
                   <>
@@ -285,6 +846,26 @@ export function StepInstaller() {
               </button>
             )}
 
+            {isAnalyzing && (
+              <div className="space-y-1.5 animate-fade-in">
+                {progress?.totalBytes ? (
+                  <div className="h-1 w-full overflow-hidden rounded-full bg-muted">
+                    <div
+                      className="h-full bg-primary transition-all"
+                      style={{ width: `${Math.min(100, (progress.bytesDone / progress.totalBytes) * 100)}%` }}
+                    />
+                  </div>
+                ) : null}
+                <button
+                  onClick={handleCancel}
+                  className="flex w-full items-center justify-center gap-1.5 text-[11px] text-muted-foreground hover:text-destructive transition-colors"
+                >
+                  <X className="h-3 w-3" />
+                  Cancel
+                </button>
+              </div>
+            )}
+
             <p className="text-center text-[11px] text-muted-foreground">
               or drag & drop a local file to compute hash
             </p>
@@ -319,6 +900,29 @@ export function StepInstaller() {
                 </div>
                 <p className="truncate text-[12px] text-muted-foreground/70">{installer.installerUrl}</p>
                 <p className="font-mono text-[11px] text-muted-foreground select-all">{installer.installerSha256}</p>
+                <InstallerListEditor label="Commands" values={installer.commands} placeholder="Command, then Enter" normalize={normalizeCommand}
+                  onChange={(commands) => updateInstaller(index, { ...installer, commands })} />
+                <InstallerListEditor label="Protocols" values={installer.protocols} placeholder="Scheme, then Enter" normalize={normalizeProtocol}
+                  onChange={(protocols) => updateInstaller(index, { ...installer, protocols })} />
+                <InstallerListEditor label="File extensions" values={installer.fileExtensions} placeholder="Extension, then Enter" normalize={normalizeFileExtension}
+                  format={(e) => `.${e}`} onChange={(fileExtensions) => updateInstaller(index, { ...installer, fileExtensions })} />
+                {installer.dependencies?.packageDependencies?.length ? (
+                  <div className="flex flex-wrap items-center gap-1 pt-0.5">
+                    <span className="text-[11px] text-muted-foreground">Depends on</span>
+                    {installer.dependencies.packageDependencies.map((dep) => (
+                      <span key={dep.packageIdentifier} className="flex items-center gap-1 rounded bg-amber-500/10 px-1.5 py-0.5 text-[10px] font-medium text-amber-400">
+                        {dep.packageIdentifier}{dep.minimumVersion ? ` ≥ ${dep.minimumVersion}` : ""}
+                        <button onClick={() => removeDependency(index, dep.packageIdentifier)} className="hover:text-destructive" title="Remove dependency">
+                          <X className="h-2.5 w-2.5" />
+                        </button>
+                      </span>
+                    ))}
+                  </div>
+                ) : null}
+                <InstallerDependenciesEditor installer={installer} onChange={(updated) => updateInstaller(index, updated)} />
+                {runsExe(installer) && <SwitchesEditor installer={installer} onChange={(updated) => updateInstaller(index, updated)} />}
+                <ReturnCodesEditor installer={installer} onChange={(updated) => updateInstaller(index, updated)} />
+                <AppsAndFeaturesEditor installer={installer} onChange={(updated) => updateInstaller(index, updated)} />
               </div>
               <button
                 onClick={() => removeInstaller(index)}
@@ -331,6 +935,10 @@ export function StepInstaller() {
         </div>
       )}
 
+      {manifest.installers.length > 0 && (
+        <DependenciesEditor value={manifest.dependencies} onChange={setDependencies} />
+      )}
+
       {autoFilled && (
         <div className="flex items-center gap-2 rounded-lg border border-primary/15 bg-primary/5 px-4 py-2.5 animate-fade-in">
           <Sparkles className="h-3.5 w-3.5 text-primary" />
@@ -340,6 +948,12 @@ export function StepInstaller() {
         </div>
       )}
 
+      {missingSwitches && (
+        <p className="text-[12px] text-destructive">
+          Enter the Silent and SilentWithProgress switches of every exe installer to continue.
+        </p>
+      )}
+
       <div className="flex items-center justify-between pt-2">
         <button
           onClick={() => setStep("home")}
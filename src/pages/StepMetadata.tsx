@@ -1,8 +1,9 @@
 import { useManifestStore } from "@/stores/manifest-store";
 import { cn } from "@/lib/utils";
-import { ArrowLeft, ArrowRight, ChevronDown, X, Globe, Check, AlertCircle, Plus, Trash2, Languages } from "lucide-react";
+import { ArrowLeft, ArrowRight, ChevronDown, X, Globe, Check, AlertCircle, Plus, Trash2, Languages, ImageIcon, Download } from "lucide-react";
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import type { Icon, IconInfo } from "@/lib/types";
 
 const locales = [
   { value: "en-US", label: "English (US)" },
@@ -22,6 +23,31 @@ const locales = [
   { value: "ar-SA", label: "\u0627\u0644\u0639\u0631\u0628\u064a\u0629 (SA)" },
 ];
 
+const legalSuffixes = new Set([
+  "inc", "llc", "ltd", "limited", "corp", "corporation", "co", "company",
+  "gmbh", "ag", "sa", "sas", "sarl", "srl", "bv", "pty", "plc", "oy", "ab",
+]);
+
+function publisherTokens(name: string): string[] {
+  return name
+    .toLowerCase()
+    .replace(/[^\p{L}\p{N}]+/gu, " ")
+    .split(" ")
+    .filter((t) => t && !legalSuffixes.has(t));
+}
+
+/** Loose comparison so "Contoso" matches "Contoso, Ltd." but not an unrelated signer. */
+function publisherMatchesSigner(publisher: string, signer: string): boolean {
+  const a = publisherTokens(publisher);
+  const b = publisherTokens(signer);
+  if (!a.length || !b.length) return true;
+  const joinedA = a.join("");
+  const joinedB = b.join("");
+  if (joinedA.includes(joinedB) || joinedB.includes(joinedA)) return true;
+  const shared = a.filter((t) => b.includes(t)).length;
+  return shared / Math.max(a.length, b.length) >= 0.5;
+}
+
 function Field({ label, required, value, onChange, placeholder, multiline, hint, error, suffix }: {
   label: string; required?: boolean; value: string; onChange: (val: string) => void;
   placeholder?: string; multiline?: boolean; hint?: string; error?: string; suffix?: React.ReactNode;
@@ -71,7 +97,7 @@ function TagsInput({ value, onChange }: { value: string[]; onChange: (tags: stri
 }
 
 export function StepMetadata() {
-  const { manifest, setPackageIdentifier, setPackageVersion, setDefaultLocale, setLocale, setStep, addAdditionalLocale, updateAdditionalLocale, removeAdditionalLocale } = useManifestStore();
+  const { manifest, detectedIcons, setPackageIdentifier, setPackageVersion, setDefaultLocale, setLocale, setStep, addAdditionalLocale, updateAdditionalLocale, removeAdditionalLocale } = useManifestStore();
   const [showOptional, setShowOptional] = useState(false);
   const [showLocales, setShowLocales] = useState(!!manifest.additionalLocales?.length);
   const locale = manifest.locale;
@@ -102,10 +128,29 @@ export function StepMetadata() {
     return null;
   })();
 
+  const signerNames = Array.from(new Set(manifest.installers.map((i) => i.signerName).filter((n): n is string => !!n)));
+  const signerMismatch = locale.publisher.trim() !== "" && signerNames.length > 0 &&
+    !signerNames.some((n) => publisherMatchesSigner(locale.publisher, n));
+
   const isValid = manifest.packageIdentifier.includes(".") && idFormatValid &&
     manifest.packageVersion.trim() !== "" && locale.publisher.trim() !== "" &&
     locale.packageName.trim() !== "" && locale.license.trim() !== "" && locale.shortDescription.trim() !== "";
 
+  // An icon is listed once the user says where it is hosted; the hash ties the URL to the file
+  const iconUrl = (icon: IconInfo) => locale.icons?.find((i) => i.iconSha256 === icon.sha256)?.iconUrl || "";
+  const setIconUrl = (icon: IconInfo, url: string) => {
+    const others = (locale.icons || []).filter((i) => i.iconSha256 !== icon.sha256);
+    const entry: Icon = {
+      iconUrl: url,
+      iconFileType: icon.fileType,
+      iconResolution: icon.resolution,
+      iconTheme: icon.theme || undefined,
+      iconSha256: icon.sha256,
+    };
+    const icons = url.trim() ? [...others, entry] : others;
+    setLocale({ icons: icons.length ? icons : undefined });
+  };
+
   const handleAddLocale = () => {
     const used = [locale.packageLocale, ...(manifest.additionalLocales || []).map((l) => l.packageLocale)];
     const available = locales.find((l) => !used.includes(l.value));
@@ -144,7 +189,8 @@ export function StepMetadata() {
       <section className="space-y-3 rounded-xl border border-border bg-card/50 p-5">
         <h3 className="text-[11px] font-bold uppercase tracking-widest text-muted-foreground">Required</h3>
         <div className="grid grid-cols-2 gap-3">
-          <Field label="Publisher" required value={locale.publisher} onChange={(v) => setLocale({ publisher: v })} placeholder="Company or author name" />
+          <Field label="Publisher" required value={locale.publisher} onChange={(v) => setLocale({ publisher: v })} placeholder="Company or author name"
+            hint={signerMismatch ? `Differs from the installer signer: ${signerNames.join(", ")}` : undefined} />
           <Field label="Package Name" required value={locale.packageName} onChange={(v) => setLocale({ packageName: v })} placeholder="My Application" />
         </div>
         <div className="grid grid-cols-2 gap-3">
@@ -176,6 +222,36 @@ export function StepMetadata() {
         )}
       </section>
 
+      {detectedIcons.length > 0 && (
+        <section className="space-y-3 rounded-xl border border-border bg-card/50 p-5">
+          <h3 className="flex items-center gap-2 text-[11px] font-bold uppercase tracking-widest text-muted-foreground">
+            <ImageIcon className="h-3 w-3" />Icons
+          </h3>
+          <p className="text-[12px] text-muted-foreground">Found in the installer. Host an icon and paste its URL to list it in the manifest.</p>
+          <div className="space-y-2">
+            {detectedIcons.map((icon) => {
+              const mime = icon.fileType === "ico" ? "image/x-icon" : "image/png";
+              const href = `data:${mime};base64,${icon.data}`;
+              return (
+                <div key={icon.sha256} className="flex items-center gap-3 rounded-lg border border-border/50 bg-background/30 px-3 py-2">
+                  <img src={href} alt="" className="h-8 w-8 shrink-0 object-contain" />
+                  <div className="w-32 shrink-0 text-[11px] text-muted-foreground">
+                    <div className="font-medium text-foreground/80">{icon.resolution} {icon.fileType}{icon.theme ? ` · ${icon.theme}` : ""}</div>
+                    <div>{(icon.fileSize / 1024).toFixed(1)} KB · {icon.sha256.slice(0, 8)}</div>
+                  </div>
+                  <input value={iconUrl(icon)} onChange={(e) => setIconUrl(icon, e.target.value)} placeholder="https://.../icon.png"
+                    className="h-8 min-w-0 flex-1 rounded-lg border border-border bg-background/50 px-2.5 text-[12px] focus:border-primary/50 focus:outline-none focus:ring-1 focus:ring-primary/20 transition-all" />
+                  <a href={href} download={`icon-${icon.resolution}.${icon.fileType}`} title="Save icon"
+                    className="rounded-md p-1.5 text-muted-foreground hover:bg-accent hover:text-foreground transition-colors">
+                    <Download className="h-3.5 w-3.5" />
+                  </a>
+                </div>
+              );
+            })}
+          </div>
+        </section>
+      )}
+
       {/* Additional Locales */}
       <section className="rounded-xl border border-border bg-card/50 overflow-hidden">
         <button onClick={() => setShowLocales(!showLocales)} className="flex w-full items-center justify-between px-5 py-3.5 text-left transition-colors hover:bg-accent/30">
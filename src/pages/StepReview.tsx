@@ -2,6 +2,7 @@ import { useEffect, useState } from "react";
 import { useManifestStore } from "@/stores/manifest-store";
 import { invoke } from "@tauri-apps/api/core";
 import { cn } from "@/lib/utils";
+import { needsSilentSwitches } from "@/lib/switches";
 import {
   ArrowLeft,
   ArrowRight,
@@ -74,12 +75,14 @@ export function StepReview() {
   }
 
   if (error) {
+    // Missing exe switches are entered with the installers, not the metadata
+    const backStep = manifest.installers.some(needsSilentSwitches) ? "installer" : "metadata";
     return (
       <div className="space-y-4">
         <div className="rounded-lg border border-destructive/20 bg-destructive/5 p-4">
           <p className="text-[13px] text-destructive">{error}</p>
         </div>
-        <button onClick={() => setStep("metadata")} className="flex items-center gap-1.5 text-[13px] text-muted-foreground hover:text-foreground">
+        <button onClick={() => setStep(backStep)} className="flex items-center gap-1.5 text-[13px] text-muted-foreground hover:text-foreground">
           <ArrowLeft className="h-3.5 w-3.5" /> Back
         </button>
       </div>
@@ -224,7 +224,11 @@ export function StepSubmit() {
     addToast("Session disconnected.", "info");
   };
 
+  // Installers whose hash disagrees with a checksum file published in their release
+  const checksumMismatches = manifest.installers.filter((i) => i.checksum?.status === "mismatch");
+
   const handleSubmit = async () => {
+    if (checksumMismatches.length > 0) return;
     const trimmedToken = token.trim();
     if (!trimmedToken) {
       setError("No active GitHub session.");
@@ -412,6 +416,21 @@ export function StepSubmit() {
         </div>
       </section>
 
+      {/* ── Checksum mismatch ── */}
+      {checksumMismatches.length > 0 && (
+        <div className="flex items-start gap-2.5 rounded-lg border border-destructive/20 bg-destructive/5 px-4 py-3 animate-fade-in">
+          <AlertCircle className="mt-0.5 h-3.5 w-3.5 text-destructive shrink-0" />
+          <div className="space-y-1 text-[12px] text-destructive/80">
+            <p>The computed SHA256 differs from the checksums published in the release. Re-download the installer before submitting.</p>
+            {checksumMismatches.map((i) => (
+              <p key={i.installerUrl} className="break-all font-mono text-[11px]">
+                {i.installerUrl}: {i.checksum!.source} lists {i.checksum!.expected}
+              </p>
+            ))}
+          </div>
+        </div>
+      )}
+
       {/* ── Error ── */}
       {error && (
         <div className="flex items-start gap-2.5 rounded-lg border border-destructive/20 bg-destructive/5 px-4 py-3 animate-fade-in">
@@ -426,7 +445,7 @@ export function StepSubmit() {
           className="flex items-center gap-1.5 rounded-lg px-4 py-2 text-[13px] font-medium text-muted-foreground transition-colors hover:bg-accent hover:text-foreground">
           <ArrowLeft className="h-3.5 w-3.5" />Back
         </button>
-        <button onClick={handleSubmit} disabled={!githubUser || isSubmitting} data-action="primary"
+        <button onClick={handleSubmit} disabled={!githubUser || isSubmitting || checksumMismatches.length > 0} data-action="primary"
           className={cn("flex items-center gap-2 rounded-lg px-5 py-2 text-[13px] font-medium transition-all duration-200",
             "bg-emerald-600 text-white hover:bg-emerald-500 active:scale-[0.98]", "disabled:cursor-not-allowed disabled:opacity-40")}>
           {isSubmitting ? (<><Loader2 className="h-3.5 w-3.5 animate-spin" />Creating PR...</>) : (<><Send className="h-3.5 w-3.5" />Submit Pull Request</>)}
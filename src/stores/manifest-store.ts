@@ -3,11 +3,14 @@ import { persist } from "zustand/middleware";
 import type {
   ManifestData,
   InstallerEntry,
+  Dependencies,
   LocaleData,
   WizardStep,
   YamlFile,
   RepoMetadata,
   ExistingManifest,
+  VersionInfo,
+  IconInfo,
 } from "@/lib/types";
 import { repoMappings } from "@/lib/repo-mappings";
 
@@ -18,12 +21,15 @@ interface ManifestStore {
   isAnalyzing: boolean;
   isSubmitting: boolean;
   isUpdate: boolean;
+  /** Icons found in the analyzed installers; not persisted. */
+  detectedIcons: IconInfo[];
 
   setStep: (step: WizardStep) => void;
   setPackageIdentifier: (id: string) => void;
   setPackageVersion: (version: string) => void;
   setDefaultLocale: (locale: string) => void;
   setMinimumOSVersion: (version: string) => void;
+  setDependencies: (dependencies: Dependencies | undefined) => void;
   addInstaller: (installer: InstallerEntry) => void;
   updateInstaller: (index: number, installer: InstallerEntry) => void;
   removeInstaller: (index: number) => void;
@@ -36,7 +42,9 @@ interface ManifestStore {
   setIsSubmitting: (value: boolean) => void;
   setIsUpdate: (value: boolean) => void;
   applyRepoMetadata: (meta: RepoMetadata) => void;
+  applyVersionInfo: (info: VersionInfo) => void;
   applyExistingManifest: (existing: ExistingManifest) => void;
+  addDetectedIcons: (icons: IconInfo[]) => void;
   reset: () => void;
 }
 
@@ -68,6 +76,7 @@ export const useManifestStore = create<ManifestStore>()(persist((set) => ({
   isAnalyzing: false,
   isSubmitting: false,
   isUpdate: false,
+  detectedIcons: [],
 
   setStep: (step) => set({ currentStep: step }),
 
@@ -85,6 +94,9 @@ export const useManifestStore = create<ManifestStore>()(persist((set) => ({
       manifest: { ...s.manifest, minimumOSVersion: version },
     })),
 
+  setDependencies: (dependencies) =>
+    set((s) => ({ manifest: { ...s.manifest, dependencies } })),
+
   addInstaller: (installer) =>
     set((s) => ({
       manifest: {
@@ -177,6 +189,24 @@ export const useManifestStore = create<ManifestStore>()(persist((set) => ({
       };
     }),
 
+  applyVersionInfo: (info) =>
+    set((s) => {
+      const m = s.manifest;
+      const loc = m.locale;
+      return {
+        manifest: {
+          ...m,
+          packageVersion: m.packageVersion || info.productVersion || "",
+          locale: {
+            ...loc,
+            publisher: loc.publisher || info.companyName || "",
+            packageName: loc.packageName || info.productName || info.fileDescription || "",
+            copyright: loc.copyright || info.legalCopyright || undefined,
+          },
+        },
+      };
+    }),
+
   applyExistingManifest: (existing) =>
     set((s) => ({
       manifest: {
@@ -202,6 +232,11 @@ export const useManifestStore = create<ManifestStore>()(persist((set) => ({
       },
     })),
 
+  addDetectedIcons: (icons) =>
+    set((s) => ({
+      detectedIcons: [...s.detectedIcons, ...icons.filter((i) => !s.detectedIcons.some((d) => d.sha256 === i.sha256))],
+    })),
+
   reset: () =>
     set({
       currentStep: "home",
@@ -210,6 +245,7 @@ export const useManifestStore = create<ManifestStore>()(persist((set) => ({
       isAnalyzing: false,
       isSubmitting: false,
       isUpdate: false,
+      detectedIcons: [],
     }),
 }), {
   name: "unicreate-manifest",